@@ -17,16 +17,22 @@ pub fn get_cache_path() -> Option<PathBuf> {
     dirs_next::cache_dir().map(|path| Path::new(&path).join(DEVRC_CACHE_DIR_NAME))
 }
 
-pub fn get_file_cache_meta(url: &Url) -> Option<PathBuf> {
-    let hash = digest(url.as_str());
+/// Get path of cached content, response depends on request headers,
+/// so they are part of the key
+pub fn get_file_cache_meta(url: &Url, headers: &[(String, String)]) -> Option<PathBuf> {
+    let mut key = url.as_str().to_string();
+    for (name, value) in headers {
+        key.push_str(&format!("\n{}: {}", name, value));
+    }
+    let hash = digest(key);
     get_cache_path().map(|path| path.join(format!("{:}.cache", hash)))
 }
 
 #[derive(Debug, Default)]
 pub struct Cache {}
 
-pub fn save(url: &Url, content: &str) -> DevrcResult<()> {
-    if let Some(file) = get_file_cache_meta(url) {
+pub fn save(url: &Url, headers: &[(String, String)], content: &str) -> DevrcResult<()> {
+    if let Some(file) = get_file_cache_meta(url, headers) {
         if let Some(dir) = file.parent() {
             if !dir.exists() {
                 fs::create_dir_all(dir)?;
@@ -41,11 +47,12 @@ pub fn save(url: &Url, content: &str) -> DevrcResult<()> {
 
 pub fn load(
     url: &Url,
+    headers: &[(String, String)],
     _loading_config: &LoadingConfig,
     _checksum: Option<&str>,
     ttl: &Duration,
 ) -> Option<String> {
-    if let Some(file) = get_file_cache_meta(url) {
+    if let Some(file) = get_file_cache_meta(url, headers) {
         if !file.exists() {
             return None;
         }
@@ -63,3 +70,24 @@ pub fn load(
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_depends_on_headers() {
+        let url = Url::parse("https://example.com/version").unwrap();
+        let headers = vec![("Authorization".to_string(), "Bearer abc".to_string())];
+
+        // Urls requested without headers keep existing cache files
+        assert_eq!(
+            get_file_cache_meta(&url, &[]),
+            get_cache_path().map(|path| path.join(format!("{}.cache", digest(url.as_str()))))
+        );
+        assert_ne!(
+            get_file_cache_meta(&url, &headers),
+            get_file_cache_meta(&url, &[])
+        );
+    }
+}
@@ -0,0 +1,533 @@
+use std::{collections::HashSet, convert::TryFrom, fmt};
+
+use tera::{
+    ast::{Expr, ExprVal, FunctionCall, Node},
+    Template,
+};
+
+use crate::{
+    environment::EnvValue,
+    errors::{DevrcError, DevrcResult},
+    raw::devrcfile::RawDevrcfile,
+    resolver::Location,
+    tasks::{exec::ExecKind, params::parse_param_name, TaskKind},
+    variables::{RawVariables, VariableKey},
+};
+
+/// Names which are always available in templates
+const BUILTIN_NAMES: [&str; 3] = ["devrc", "loop", "__tera_context"];
+
+/// Undefined names or syntax error found in template
+#[derive(Debug, Clone)]
+pub struct TemplateIssue {
+    pub location: Location,
+    pub task: Option<String>,
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Location::LocalFile(path) => write!(f, "{}", path.display())?,
+            Location::Remote { url, .. } => write!(f, "{}", url)?,
+            Location::StdIn => write!(f, "<stdin>")?,
+            Location::None => write!(f, "<unknown>")?,
+        }
+        if let Some(task) = &self.task {
+            write!(f, ": task `{}`", task)?;
+        }
+        write!(f, ": {}: {}", self.field, self.message)
+    }
+}
+
+/// Get names which are used by template but not defined
+pub fn get_undefined_names(
+    name: &str,
+    template: &str,
+    defined: &HashSet<String>,
+) -> DevrcResult<Vec<String>> {
+    let template = Template::new(name, None, template)?;
+
+    let mut collector = NamesCollector {
+        defined,
+        locals: BUILTIN_NAMES.iter().map(|name| name.to_string()).collect(),
+        undefined: Vec::new(),
+        optional: false,
+    };
+    collector.visit_nodes(&template.ast);
+
+    Ok(collector.undefined)
+}
+
+/// Get all names which template refers to, including optional ones
+pub fn get_used_names(name: &str, template: &str) -> DevrcResult<Vec<String>> {
+    let template = Template::new(name, None, template)?;
+
+    let defined = HashSet::new();
+    let mut collector = NamesCollector {
+        defined: &defined,
+        locals: BUILTIN_NAMES.iter().map(|name| name.to_string()).collect(),
+        undefined: Vec::new(),
+        optional: true,
+    };
+    collector.visit_nodes(&template.ast);
+
+    Ok(collector.undefined)
+}
+
+struct NamesCollector<'a> {
+    defined: &'a HashSet<String>,
+    /// Names set inside template
+    locals: HashSet<String>,
+    undefined: Vec<String>,
+    /// Collect names with `default` filter and `is` tests too
+    optional: bool,
+}
+
+impl NamesCollector<'_> {
+    fn visit_nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.visit_node(node);
+        }
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::VariableBlock(_, expr) => self.visit_expr(expr),
+            Node::Set(_, set) => {
+                self.visit_expr(&set.value);
+                self.locals.insert(set.key.clone());
+            }
+            Node::FilterSection(_, section, _) => {
+                self.visit_call(&section.filter);
+                self.visit_nodes(&section.body);
+            }
+            Node::Block(_, block, _) => self.visit_nodes(&block.body),
+            Node::Forloop(_, forloop, _) => {
+                self.visit_expr(&forloop.container);
+                self.locals.extend(forloop.key.iter().cloned());
+                self.locals.insert(forloop.value.clone());
+                self.visit_nodes(&forloop.body);
+                if let Some(body) = &forloop.empty_body {
+                    self.visit_nodes(body);
+                }
+            }
+            Node::If(condition, _) => {
+                for (_, expr, body) in &condition.conditions {
+                    self.visit_expr(expr);
+                    self.visit_nodes(body);
+                }
+                if let Some((_, body)) = &condition.otherwise {
+                    self.visit_nodes(body);
+                }
+            }
+            // Macros arguments are checked where macros are called
+            _ => {}
+        }
+    }
+
+    fn visit_call(&mut self, call: &FunctionCall) {
+        for expr in call.args.values() {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        for filter in &expr.filters {
+            self.visit_call(filter);
+        }
+
+        // Value can be missing if default is given
+        if expr.has_default_filter() && !self.optional {
+            return;
+        }
+        self.visit_value(&expr.val);
+    }
+
+    fn visit_value(&mut self, value: &ExprVal) {
+        match value {
+            ExprVal::Ident(ident) => self.visit_ident(ident),
+            ExprVal::Math(math) => {
+                self.visit_expr(&math.lhs);
+                self.visit_expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.visit_expr(&logic.lhs);
+                self.visit_expr(&logic.rhs);
+            }
+            ExprVal::In(value) => {
+                self.visit_expr(&value.lhs);
+                self.visit_expr(&value.rhs);
+            }
+            // `is defined` test checks possibly undefined value
+            ExprVal::Test(test) => {
+                if self.optional {
+                    self.visit_ident(&test.ident);
+                }
+                for expr in &test.args {
+                    self.visit_expr(expr);
+                }
+            }
+            ExprVal::MacroCall(call) => {
+                for expr in call.args.values() {
+                    self.visit_expr(expr);
+                }
+            }
+            ExprVal::FunctionCall(call) => self.visit_call(call),
+            ExprVal::Array(items) => {
+                for expr in items {
+                    self.visit_expr(expr);
+                }
+            }
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    self.visit_value(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check root of `name.attr[index]` and identifiers used as index
+    fn visit_ident(&mut self, ident: &str) {
+        let mut parts = ident.split(['[', ']']);
+
+        if let Some(root) = parts.next() {
+            self.check_name(root.split('.').next().unwrap_or_default());
+        }
+
+        for part in parts.step_by(2) {
+            let part = part.trim();
+            let is_literal = part.starts_with(['"', '\'', '`'])
+                || part.starts_with(|value: char| value.is_ascii_digit());
+
+            if !part.is_empty() && !is_literal {
+                self.visit_ident(part);
+            }
+        }
+    }
+
+    fn check_name(&mut self, name: &str) {
+        if name.is_empty()
+            || self.locals.contains(name)
+            || self.defined.contains(name)
+            || self.undefined.iter().any(|value| value == name)
+        {
+            return;
+        }
+        self.undefined.push(name.to_string());
+    }
+}
+
+/// Checks templates of loaded files
+#[derive(Debug, Default)]
+pub struct TemplatesChecker {
+    /// Names of global variables defined in already checked files
+    global: HashSet<String>,
+    /// Checked task loads variables files, so its names are known only at execution
+    names_unknown: bool,
+    pub issues: Vec<TemplateIssue>,
+}
+
+impl TemplatesChecker {
+    /// Check global variables and environment variables of file
+    pub fn check_globals(&mut self, file: &RawDevrcfile) -> DevrcResult<()> {
+        let mut defined = self.global.clone();
+        defined.extend(file.files_variables.variables.keys().cloned());
+
+        self.check_variables(file, None, "variables", &file.variables, &mut defined)?;
+        self.check_environment(file, None, "environment", &file.environment.vars, &defined)?;
+
+        self.global = defined;
+        Ok(())
+    }
+
+    /// Check tasks of file, `global` contains all global variables
+    pub fn check_tasks(
+        &mut self,
+        file: &RawDevrcfile,
+        global: &HashSet<String>,
+    ) -> DevrcResult<()> {
+        let hooks = [
+            ("after_script", &file.after_script),
+            ("before_script", &file.before_script),
+            ("before_task", &file.before_task),
+            ("after_task", &file.after_task),
+        ];
+        let tasks = file
+            .tasks
+            .items
+            .iter()
+            .map(|(name, task)| (name.as_str(), task));
+        let hooks = hooks
+            .iter()
+            .filter_map(|(name, task)| Some((*name, task.as_ref()?.as_ref()?)));
+
+        for (name, task) in tasks.chain(hooks) {
+            self.check_task(file, name, task, global)?;
+        }
+        Ok(())
+    }
+
+    fn check_task(
+        &mut self,
+        file: &RawDevrcfile,
+        name: &str,
+        task: &TaskKind,
+        global: &HashSet<String>,
+    ) -> DevrcResult<()> {
+        let mut defined = global.clone();
+        let task_name = Some(name);
+
+        let complex = match task {
+            TaskKind::Command(exec) => {
+                return self.check_template(file, task_name, "exec", exec, &defined)
+            }
+            TaskKind::Commands(exec) => {
+                return self.check_exec(file, task_name, exec, &defined);
+            }
+            TaskKind::ComplexCommand(complex) => complex,
+            TaskKind::Empty | TaskKind::Include(_) => return Ok(()),
+        };
+
+        for (key, param) in complex.get_parameters(&[])? {
+            let (param_name, _) = parse_param_name(&key);
+            if let Some(default) = param.get_default() {
+                let field = format!("params.{}", param_name);
+                self.check_template(file, task_name, &field, default, &defined)?;
+            }
+            defined.insert(param_name.to_string());
+        }
+
+        // Files are not read by check, only syntax of templates is checked
+        self.names_unknown = !complex.get_vars_file().is_empty();
+
+        self.check_variables(
+            file,
+            task_name,
+            "variables",
+            complex.get_variables(),
+            &mut defined,
+        )?;
+        self.check_environment(
+            file,
+            task_name,
+            "environment",
+            &complex.get_environment().vars,
+            &defined,
+        )?;
+
+        for subtask in &complex.subtasks {
+            let mut subtask_defined = defined.clone();
+            let field = format!("subtasks.{}.variables", subtask.name);
+            self.check_variables(
+                file,
+                task_name,
+                &field,
+                &subtask.variables,
+                &mut subtask_defined,
+            )?;
+
+            let field = format!("subtasks.{}.environment", subtask.name);
+            self.check_environment(
+                file,
+                task_name,
+                &field,
+                &subtask.environment.vars,
+                &subtask_defined,
+            )?;
+        }
+
+        self.check_exec(file, task_name, &complex.exec, &defined)?;
+        self.names_unknown = false;
+        Ok(())
+    }
+
+    fn check_exec(
+        &mut self,
+        file: &RawDevrcfile,
+        task: Option<&str>,
+        exec: &ExecKind,
+        defined: &HashSet<String>,
+    ) -> DevrcResult<()> {
+        match exec {
+            ExecKind::Empty => Ok(()),
+            ExecKind::String(template) => {
+                self.check_template(file, task, "exec", template, defined)
+            }
+            ExecKind::List(templates) => {
+                for (idx, template) in templates.iter().enumerate() {
+                    let field = format!("exec[{}]", idx);
+                    self.check_template(file, task, &field, template, defined)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn check_variables(
+        &mut self,
+        file: &RawDevrcfile,
+        task: Option<&str>,
+        section: &str,
+        variables: &RawVariables,
+        defined: &mut HashSet<String>,
+    ) -> DevrcResult<()> {
+        for (original_key, value) in &variables.vars {
+            let key = VariableKey::try_from(original_key.clone())?;
+
+            if !key.raw {
+                let field = format!("{}.{}", section, key.name);
+                for template in value.get_templates() {
+                    self.check_template(file, task, &field, &template, defined)?;
+                }
+            }
+            defined.insert(key.name);
+        }
+        Ok(())
+    }
+
+    fn check_environment(
+        &mut self,
+        file: &RawDevrcfile,
+        task: Option<&str>,
+        section: &str,
+        environment: &indexmap::IndexMap<String, EnvValue>,
+        defined: &HashSet<String>,
+    ) -> DevrcResult<()> {
+        for (name, value) in environment {
+            let field = format!("{}.{}", section, name);
+            for template in value.get_templates() {
+                self.check_template(file, task, &field, template, defined)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_template(
+        &mut self,
+        file: &RawDevrcfile,
+        task: Option<&str>,
+        field: &str,
+        template: &str,
+        defined: &HashSet<String>,
+    ) -> DevrcResult<()> {
+        let message = match get_undefined_names(field, template, defined) {
+            Ok(names) if names.is_empty() || self.names_unknown => return Ok(()),
+            Ok(names) => format!("undefined `{}`", names.join("`, `")),
+            Err(DevrcError::RenderError(error)) => format!("invalid template: {}", error),
+            Err(error) => return Err(error),
+        };
+
+        self.issues.push(TemplateIssue {
+            location: file.location.clone(),
+            task: task.map(|value| value.to_string()),
+            field: field.to_string(),
+            message,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn undefined(template: &str, defined: &[&str]) -> Vec<String> {
+        let defined = defined.iter().map(|value| value.to_string()).collect();
+        get_undefined_names("test", template, &defined).unwrap()
+    }
+
+    #[test]
+    fn test_undefined_names() {
+        assert_eq!(
+            undefined("{{ version }}", &["version"]),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            undefined("{{ verison }} {{ verison }}", &["version"]),
+            vec!["verison"]
+        );
+        assert_eq!(
+            undefined(
+                "{% for s in services %}{{ s }} {{ ports[s] }} {{ loop.index }}{% endfor %}",
+                &["services", "ports"]
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            undefined(
+                r#"{% set x = 1 %}{{ x + y }} {{ z | default(value="") }} {% if w is defined %}{% endif %}"#,
+                &[]
+            ),
+            vec!["y"]
+        );
+        assert_eq!(
+            undefined(
+                r#"{{ config.a["b"] }} {{ items[idx] }} {{ path_join(path=dir) }} {{ devrc.args }}"#,
+                &["config", "items"]
+            ),
+            vec!["idx", "dir"]
+        );
+        assert!(get_undefined_names("test", "{{ }", &HashSet::new()).is_err());
+        assert_eq!(
+            get_used_names(
+                "test",
+                r#"{{ x + y }} {{ z | default(value="") }} {% if w is defined %}{% endif %}"#
+            )
+            .unwrap(),
+            vec!["x", "y", "z", "w"]
+        );
+    }
+
+    #[test]
+    fn test_check_devrcfile() {
+        let file: RawDevrcfile = serde_yaml::from_str(
+            r#"
+variables:
+  version: "1.0"
+  tag: "{{ verison }}"
+
+environment:
+  VERSION: "{{ version }}"
+
+deploy:
+  exec:
+    - echo {{ target }} {{ version }} {{ replicas }}
+    - echo {{ other }}
+  params:
+    target: "{{ region }}"
+    replicas: "1"
+
+build: echo {{ version }}
+
+release:
+  exec: echo {{ from_file }}
+  vars_file: release.yml
+"#,
+        )
+        .unwrap();
+        let file = file.with_location(Location::LocalFile(PathBuf::from("Devrcfile")));
+
+        let mut checker = TemplatesChecker::default();
+        checker.check_globals(&file).unwrap();
+        checker.check_tasks(&file, &checker.global.clone()).unwrap();
+
+        let issues: Vec<String> = checker
+            .issues
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                "Devrcfile: variables.tag: undefined `verison`",
+                "Devrcfile: task `deploy`: params.target: undefined `region`",
+                "Devrcfile: task `deploy`: exec[1]: undefined `other`",
+            ]
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use std::{io::Cursor, path::PathBuf};
+use std::{
+    ffi::{OsStr, OsString},
+    io::Cursor,
+    path::PathBuf,
+};
 use structopt::{
     clap::{AppSettings, Shell},
     StructOpt,
@@ -6,7 +10,11 @@ use structopt::{
 
 use std::error::Error;
 
-use crate::errors::{DevrcError, DevrcResult};
+use crate::{
+    errors::{DevrcError, DevrcResult},
+    graph::GraphFormat,
+    tasks::args_parser::ARGUMENTS_END,
+};
 
 pub fn get_crate_version() -> &'static str {
     env!("CARGO_PKG_VERSION")
@@ -37,6 +45,7 @@ where
 #[structopt(after_help = "Wish you productive coding!
 Visit https://github.com/devrc-hub/devrc to get more info about devrc.")]
 #[structopt(setting = AppSettings::ColoredHelp)]
+#[structopt(setting = AppSettings::AllowLeadingHyphen)]
 pub struct CommandLine {
     /// Specify an alternate Devrcfile files
     /// (default: Devrcfile)
@@ -45,6 +54,7 @@ pub struct CommandLine {
         name = "CONFIG",
         short = "f",
         long = "f",
+        number_of_values = 1,
         verbatim_doc_comment
     )]
     pub configs: Vec<PathBuf>,
@@ -65,6 +75,11 @@ pub struct CommandLine {
     #[structopt(long = "variables")]
     pub list_vars: bool,
 
+    /// Show where <NAME> variable is defined and its overrides,
+    /// task name can be given to show task variable
+    #[structopt(long = "why", name = "NAME", requires = "list-vars")]
+    pub why: Option<String>,
+
     /// Show global environment variables
     #[structopt(long = "evariables")]
     pub list_env_vars: bool,
@@ -74,7 +89,6 @@ pub struct CommandLine {
     pub completions: Option<Shell>,
 
     #[structopt(name = "TASKS OR ARGS")]
-    // //#[structopt(name = "TASKS", raw(setting = "AppSettings::AllowLeadingHyphen"))]
     pub rest: Vec<String>,
 
     /// Force to use global .devrc file
@@ -85,10 +99,30 @@ pub struct CommandLine {
     #[structopt(long = "--dry-run")]
     pub dry_run: bool,
 
+    /// Answer yes to all confirmations
+    #[structopt(short = "y", long = "--yes")]
+    pub yes: bool,
+
+    /// Don't ask user to enter missing task arguments
+    #[structopt(long = "--no-input")]
+    pub no_input: bool,
+
+    /// Check that templates use only defined variables
+    #[structopt(long = "--check")]
+    pub check: bool,
+
     /// Describe task
     #[structopt(long = "--describe")]
     pub describe: bool,
 
+    /// Print tasks dependencies and subtasks graph in <FORMAT>
+    #[structopt(long = "--graph", name = "FORMAT", possible_values = &["dot", "mermaid"], case_insensitive = true)]
+    pub graph: Option<GraphFormat>,
+
+    /// Show hooks in tasks graph
+    #[structopt(long = "--graph-hooks")]
+    pub graph_hooks: bool,
+
     /// Show debug info
     #[structopt(long = "--dbg-runner", hidden = true)]
     pub dbg: bool,
@@ -103,11 +137,45 @@ pub struct CommandLine {
     pub quiet: bool,
 
     /// Override <VARIABLE> with <VALUE>
-    #[structopt(long = "--set", parse(try_from_str = parse_key_val), name="VAR=VALUE")]
+    #[structopt(long = "--set", parse(try_from_str = parse_key_val), name="VAR=VALUE", number_of_values = 1)]
     pub set: Vec<(String, String)>,
+
+    /// Load variables from JSON, YAML or TOML <FILE>
+    #[structopt(
+        long = "vars-file",
+        parse(from_os_str),
+        name = "FILE",
+        number_of_values = 1
+    )]
+    pub vars_files: Vec<PathBuf>,
 }
 
 impl CommandLine {
+    /// Parse given command line.
+    ///
+    /// Clap consumes `--`, it's restored in tasks values
+    /// if it follows tasks names to mark the end of task arguments.
+    pub fn parse_from<I>(args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let mut opt = Self::from_iter(&args);
+
+        if let Some(position) = args
+            .iter()
+            .position(|arg| arg.as_os_str() == OsStr::new(ARGUMENTS_END))
+        {
+            let passed = args.len() - position - 1;
+            match opt.rest.len().checked_sub(passed) {
+                Some(index) if index > 0 => opt.rest.insert(index, ARGUMENTS_END.to_string()),
+                _ => {}
+            }
+        }
+        opt
+    }
+
     pub fn generate_completions(shell: Shell) {
         let mut cursor = Cursor::new(Vec::new());
         Self::clap().gen_completions_to(env!("CARGO_PKG_NAME"), shell, &mut cursor);
@@ -129,5 +197,35 @@ pub enum Subcommands {
 }
 
 pub fn parse_args() -> CommandLine {
-    CommandLine::from_args()
+    CommandLine::parse_from(std::env::args_os())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_after_tasks_names() {
+        let opt = CommandLine::parse_from(&["devrc", "hello", "--dry-run", "-v", "--set", "a=1"]);
+        assert!(opt.dry_run);
+        assert_eq!(opt.verbose, 1);
+        assert_eq!(opt.set, vec![("a".to_string(), "1".to_string())]);
+        assert_eq!(opt.rest, vec!["hello"]);
+
+        let opt =
+            CommandLine::parse_from(&["devrc", "hello", "--name=Alex", "-f", "Devrcfile", "build"]);
+        assert_eq!(opt.configs, vec![PathBuf::from("Devrcfile")]);
+        assert_eq!(opt.rest, vec!["hello", "--name=Alex", "build"]);
+    }
+
+    #[test]
+    fn test_values_after_arguments_end() {
+        let opt = CommandLine::parse_from(&["devrc", "test", "--", "--dry-run", "-v"]);
+        assert!(!opt.dry_run);
+        assert_eq!(opt.verbose, 0);
+        assert_eq!(opt.rest, vec!["test", "--", "--dry-run", "-v"]);
+
+        let opt = CommandLine::parse_from(&["devrc", "-f", "Devrcfile", "--", "test"]);
+        assert_eq!(opt.rest, vec!["test"]);
+    }
 }
@@ -3,15 +3,24 @@ use std::{env, fmt::Debug, path::PathBuf, time::Duration};
 
 use devrc_core::logging::LogLevel;
 
+/// Prefix of environment variables which override devrc variables
+pub const DEFAULT_VARIABLES_ENV_PREFIX: &str = "DEVRC_VAR_";
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub current_dir: Option<PathBuf>,
     pub interpreter: InterpreterKind,
     pub log_level: LogLevel,
     pub dry_run: bool,
+    pub assume_yes: bool,
+    pub no_input: bool,
     pub default: Vec<String>,
     pub plugins: indexmap::IndexMap<String, PathBuf>,
     pub cache_ttl: Option<Duration>,
+    pub env_clean: bool,
+    pub env_passthrough: Vec<String>,
+    pub strict_templates: bool,
+    pub variables_env_prefix: Option<String>,
 }
 
 impl Default for Config {
@@ -19,11 +28,17 @@ impl Default for Config {
         Config {
             current_dir: env::current_dir().ok(),
             dry_run: false,
+            assume_yes: false,
+            no_input: false,
             interpreter: InterpreterKind::default(),
             log_level: LogLevel::Info,
             default: vec![],
             plugins: indexmap::IndexMap::new(),
             cache_ttl: None,
+            env_clean: false,
+            env_passthrough: vec![],
+            strict_templates: false,
+            variables_env_prefix: Some(DEFAULT_VARIABLES_ENV_PREFIX.to_string()),
         }
     }
 }
@@ -1,5 +1,45 @@
 use serde::{Deserialize, Deserializer};
 
+/// YAML scalar value which is used as string
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Scalar {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl From<Scalar> for String {
+    fn from(value: Scalar) -> Self {
+        match value {
+            Scalar::String(value) => value,
+            Scalar::Integer(value) => value.to_string(),
+            Scalar::Float(value) => value.to_string(),
+            Scalar::Bool(value) => value.to_string(),
+        }
+    }
+}
+
+// Deserialize optional scalar value as string
+pub fn deserialize_option_scalar<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Scalar>::deserialize(deserializer)?.map(String::from))
+}
+
+// Deserialize optional list of scalar values as list of strings
+pub fn deserialize_option_scalar_list<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Vec<Scalar>>::deserialize(deserializer)?
+        .map(|values| values.into_iter().map(String::from).collect()))
+}
+
 // Any value that is present is considered Some value, including null.
 pub fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
 where
@@ -8,3 +48,21 @@ where
 {
     Deserialize::deserialize(deserializer).map(Some)
 }
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+// Deserialize single string or list of strings as list
+pub fn deserialize_string_or_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::String(value) => vec![value],
+        StringOrList::List(values) => values,
+    })
+}
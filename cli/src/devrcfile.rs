@@ -1,22 +1,29 @@
 use std::{cell::RefCell, cmp, rc::Rc};
 
 use crate::{
+    check::{TemplateIssue, TemplatesChecker},
     config::Config,
-    environment::{Environment, RawEnvironment},
+    env_file::LoadedEnvironment,
+    environment::{EnvValue, Environment, RawEnvironment},
     errors::{DevrcError, DevrcResult},
+    origin::Origin,
     raw::{
         config::{DefaultOption, RawConfig},
         devrcfile::{Kind, RawDevrcfile},
     },
     scope::{child_scope, Scope},
     tasks::{
+        args_parser::parse_command_line,
         arguments::{extract_task_args, TaskArguments},
         Task, TaskKind, Tasks,
     },
+    template,
     variables::RawVariables,
+    vars_file::LoadedVariables,
 };
 
 use devrc_core::workshop::Designer;
+use indexmap::IndexSet;
 use unicode_width::UnicodeWidthStr;
 
 use devrc_plugins::execution::ExecutionPluginManager;
@@ -39,6 +46,9 @@ pub struct Devrcfile {
     pub scope: Rc<RefCell<Scope>>,
 
     pub execution_plugin_registry: Rc<RefCell<ExecutionPluginManager>>,
+
+    /// Loaded files which templates are checked by `--check`
+    files: Vec<RawDevrcfile>,
 }
 
 impl Devrcfile {
@@ -122,6 +132,22 @@ impl Devrcfile {
             if let Some(duration) = config.cache_ttl {
                 self.config.cache_ttl = Some(duration)
             }
+
+            if let Some(env_clean) = config.env_clean {
+                self.config.env_clean = env_clean.unwrap_or(false);
+            }
+
+            if let Some(env_passthrough) = config.env_passthrough {
+                self.config.env_passthrough = env_passthrough;
+            }
+
+            if let Some(strict_templates) = config.strict_templates {
+                self.config.strict_templates = strict_templates;
+            }
+
+            if let Some(prefix) = config.variables_env_prefix {
+                self.config.variables_env_prefix = prefix;
+            }
         }
 
         Ok(())
@@ -132,21 +158,62 @@ impl Devrcfile {
         Ok(())
     }
 
+    pub fn setup_assume_yes(&mut self, assume_yes: bool) -> DevrcResult<()> {
+        self.config.assume_yes = assume_yes;
+        Ok(())
+    }
+
+    pub fn setup_no_input(&mut self, no_input: bool) -> DevrcResult<()> {
+        self.config.no_input = no_input;
+        Ok(())
+    }
+
     pub fn setup_log_level(&mut self, level: devrc_core::logging::LogLevel) -> DevrcResult<()> {
         self.config.log_level = level;
         Ok(())
     }
 
     // Add variables to global scope
+    /// Add variables to global scope, environment variables of loaded files
+    /// which refer to overridden variables are rendered again
     pub fn process_variables(&mut self, variables: RawVariables) -> DevrcResult<()> {
         let mut global_scope = (*self.scope)
             .try_borrow_mut()
             .map_err(|_| DevrcError::RuntimeError)?;
-        global_scope.process_raw_vars(&variables)
+        let changed = global_scope.process_override_vars(&variables)?;
+
+        for file in &self.files {
+            global_scope.rerender_env_vars(&file.environment, &changed)?;
+        }
+        Ok(())
+    }
+
+    /// Add variables from data files to global scope
+    pub fn process_loaded_variables(
+        &mut self,
+        variables: &LoadedVariables,
+        origin: &Origin,
+    ) -> DevrcResult<()> {
+        let mut global_scope = (*self.scope)
+            .try_borrow_mut()
+            .map_err(|_| DevrcError::RuntimeError)?;
+        global_scope.process_loaded_vars(variables, origin)?;
+
+        let mut changed: IndexSet<String> = variables.variables.keys().cloned().collect();
+        let dependents = global_scope.get_dependents(&changed)?;
+        changed.extend(dependents.into_iter().map(|key| key.name));
+
+        for file in &self.files {
+            global_scope.rerender_env_vars(&file.environment, &changed)?;
+        }
+        Ok(())
     }
 
     // Add variables to global scope
-    pub fn process_env_variables(&mut self, variables: RawEnvironment<String>) -> DevrcResult<()> {
+    pub fn process_env_variables(
+        &mut self,
+        variables: RawEnvironment<EnvValue>,
+    ) -> DevrcResult<()> {
         let mut global_scope = (*self.scope)
             .try_borrow_mut()
             .map_err(|_| DevrcError::RuntimeError)?;
@@ -156,11 +223,19 @@ impl Devrcfile {
     pub fn process_env_files_variables(
         &mut self,
         variables: Environment<String>,
+        secrets: IndexSet<String>,
+        origin: &Origin,
     ) -> DevrcResult<()> {
         let mut global_scope = (*self.scope)
             .try_borrow_mut()
             .map_err(|_| DevrcError::RuntimeError)?;
-        global_scope.process_rendered_env_vars(&variables)
+        global_scope.process_loaded_env(
+            &LoadedEnvironment {
+                environment: variables,
+                secrets,
+            },
+            origin,
+        )
     }
 
     // pub fn add_env_file(&mut self, files: EnvFile, base_path: Option<&PathBuf>) -> DevrcResult<()> {
@@ -184,8 +259,20 @@ impl Devrcfile {
     /// Add objects from given `RawDevrcfile` to current object
     ///
     /// this method implement merge stategy
-    pub fn add_raw_devrcfile(&mut self, file: RawDevrcfile, kind: &Kind) -> DevrcResult<()> {
-        self.add_config(file.config, kind)?;
+    pub fn add_raw_devrcfile(&mut self, mut file: RawDevrcfile, kind: &Kind) -> DevrcResult<()> {
+        self.add_config(file.config.clone(), kind)?;
+
+        // Global templates of file are rendered relative to it
+        (*self.scope)
+            .try_borrow_mut()
+            .map_err(|_| DevrcError::RuntimeError)?
+            .directory = file.location.get_directory();
+
+        file.setup_execution(&self.config, &self.execution_plugin_registry);
+
+        template::add_templates(&file.loaded_templates)?;
+
+        self.files.push(file.clone());
 
         // Field value present or null
         if let Some(value) = file.after_script {
@@ -208,10 +295,17 @@ impl Devrcfile {
             self.add_task(name, task)?;
         }
 
-        self.process_env_files_variables(file.files_environment)?;
+        self.process_loaded_variables(&file.files_variables, &file.vars_file.origin)?;
 
         self.process_variables(file.variables)?;
 
+        // Env files references can use variables of file
+        self.process_env_files_variables(
+            file.files_environment,
+            file.files_secrets,
+            &Origin::file(&file.location, None, "env_file"),
+        )?;
+
         self.process_env_variables(file.environment)?;
 
         Ok(())
@@ -220,7 +314,7 @@ impl Devrcfile {
     /// Get task doct objects
     // pub fn get_tasks_docs(&self) -> std::iter::Map<indexmap::map::Iter<String, crate::tasks::TaskKind>, |(&String, &crate::tasks::TaskKind)| -> ()> {
     pub fn get_tasks_docs(&self) -> impl Iterator<Item = (&String, &TaskKind)> {
-        self.tasks.items.iter().map(|(key, value)| (key, value))
+        self.tasks.items.iter()
     }
 
     pub fn get_max_taskname_width(&self) -> (usize, usize) {
@@ -277,8 +371,19 @@ impl Devrcfile {
         name: &str,
         task: &TaskKind,
         args: TaskArguments,
+        rest: &[String],
         parent_scope: Rc<RefCell<Scope>>,
     ) -> DevrcResult<()> {
+        // Ask user before anything of the task is executed
+        task.confirm(
+            name,
+            Rc::clone(&parent_scope),
+            &args,
+            rest,
+            &self.config,
+            &self.designer,
+        )?;
+
         // Execute dependencies tasks
         if let Some(deps) = task.get_dependencies() {
             self.config.log_level.debug(
@@ -292,18 +397,17 @@ impl Devrcfile {
                     dependency_task_name,
                     dependency_task,
                     args.clone(),
+                    &[],
                     Rc::clone(&parent_scope),
                 )?;
             }
         }
 
-        // Execute subtasks before main task
-        let scope = Rc::new(RefCell::new(task.get_scope(
-            name,
-            Rc::clone(&parent_scope),
-            &args,
-        )?));
+        let mut scope = task.get_scope(name, Rc::clone(&parent_scope), &args)?;
+        scope.args = rest.to_vec();
+        let scope = Rc::new(RefCell::new(scope));
 
+        // Execute subtasks before main task
         if let Some(subtasks) = task.get_subtasks() {
             self.config.log_level.debug(
                 &format!("\n==> Running subtasks `{}`: ...", &name),
@@ -319,12 +423,20 @@ impl Devrcfile {
                 );
 
                 subtask_scope.process_raw_vars(&subtask_call.variables)?;
+
+                if !subtask_call.env_file.is_empty() {
+                    subtask_scope.process_loaded_env(
+                        &subtask_call.env_file.load()?,
+                        &subtask_call.env_file.origin,
+                    )?;
+                }
                 subtask_scope.process_raw_env_vars(&subtask_call.environment)?;
 
                 self.run_task(
                     &subtask_call.name,
                     subtask,
                     args.clone(),
+                    &[],
                     Rc::new(RefCell::new(subtask_scope)),
                 )?;
             }
@@ -345,8 +457,48 @@ impl Devrcfile {
         Ok(())
     }
 
+    /// Find undefined names in templates of all loaded files
+    pub fn get_templates_issues(&self) -> DevrcResult<Vec<TemplateIssue>> {
+        let mut checker = TemplatesChecker::default();
+
+        for file in &self.files {
+            checker.check_globals(file)?;
+        }
+
+        let global = (*self.scope)
+            .try_borrow()
+            .map_err(|_| DevrcError::RuntimeError)?
+            .variables
+            .keys()
+            .map(|key| key.get_name())
+            .collect();
+
+        for file in &self.files {
+            checker.check_tasks(file, &global)?;
+        }
+
+        Ok(checker.issues)
+    }
+
+    /// Print templates issues, fail if there are any
+    pub fn check_templates(&self) -> DevrcResult<()> {
+        let issues = self.get_templates_issues()?;
+
+        for issue in &issues {
+            eprintln!("{}", issue);
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(DevrcError::TemplatesCheckFailed(issues.len()))
+        }
+    }
+
     pub fn run(&self, params: &[String]) -> DevrcResult<()> {
-        let mut i = 0;
+        if self.config.strict_templates {
+            self.check_templates()?;
+        }
 
         let tasks_names = if params.is_empty() {
             self.config.default.clone()
@@ -354,18 +506,29 @@ impl Devrcfile {
             params.to_vec()
         };
 
-        let mut tasks: Vec<(&str, &TaskKind, TaskArguments)> = Vec::new();
+        let groups = parse_command_line(&tasks_names)?;
 
-        while i < tasks_names.len() {
-            let name = &tasks_names[i];
+        let mut tasks: Vec<(&str, &TaskKind, TaskArguments, &[String])> = Vec::new();
 
-            let task = self.find_task(&tasks_names[i])?;
+        for group in groups.iter() {
+            let names = &group.tokens;
+            let mut i = 0;
 
-            let (counter, args) = extract_task_args(task, &tasks_names[(i + 1)..], self)?;
+            while i < names.len() {
+                let name = &names[i];
 
-            tasks.push((name, task, args));
+                let task = self.find_task(name)?;
 
-            i += 1 + counter;
+                let (counter, args) =
+                    extract_task_args(task, &names[(i + 1)..], group.explicit, self)?;
+
+                i += 1 + counter;
+
+                // Arguments after `--` belong to the last task of group
+                let rest: &[String] = if i >= names.len() { &group.rest } else { &[] };
+
+                tasks.push((name, task, args, rest));
+            }
         }
 
         self.detect_circular_dependencies(
@@ -379,8 +542,8 @@ impl Devrcfile {
 
         let scope = &self.scope;
 
-        for (name, task, args) in tasks {
-            self.run_task(name, task, args, Rc::clone(scope))?;
+        for (name, task, args, rest) in tasks {
+            self.run_task(name, task, args, rest, Rc::clone(scope))?;
         }
 
         self.run_hook("after_script", None)?;
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+
+use crate::{
+    environment::Environment,
+    errors::{DevrcError, DevrcResult},
+};
+
+/// Replaces `$` which starts reference, dotenv parser keeps it as is
+const REFERENCE_MARK: char = '\u{1}';
+
+/// Part of env file value
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Reference {
+        name: String,
+        default: Option<String>,
+    },
+}
+
+impl Part {
+    /// Restore marked form of part to resolve it later
+    fn to_marked(&self) -> String {
+        match self {
+            Part::Text(text) => text.clone(),
+            Part::Reference {
+                name,
+                default: Some(default),
+            } => format!("{}{{{}:-{}}}", REFERENCE_MARK, name, default),
+            Part::Reference {
+                name,
+                default: None,
+            } => format!("{}{{{}}}", REFERENCE_MARK, name),
+        }
+    }
+}
+
+/// Mark `$` which may start reference before env file is parsed.
+///
+/// Comments, single quoted values and escaped `\$` are kept literal.
+pub fn mark_references(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for line in content.split_inclusive('\n') {
+        let value = if quote.is_some() {
+            line
+        } else if line.trim_start().starts_with('#') {
+            result.push_str(line);
+            continue;
+        } else if let Some(index) = line.find('=') {
+            result.push_str(&line[..=index]);
+            &line[index + 1..]
+        } else {
+            result.push_str(line);
+            continue;
+        };
+
+        for (index, c) in value.char_indices() {
+            if escaped {
+                escaped = false;
+                result.push(c);
+                continue;
+            }
+
+            match (quote, c) {
+                (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+                (Some('\''), _) => {}
+                (None, '\'') | (None, '"') => quote = Some(c),
+                (_, '\\') => escaped = true,
+                (_, '$') => {
+                    result.push(REFERENCE_MARK);
+                    continue;
+                }
+                // Rest of line is inline comment
+                (None, ' ') | (None, '\t') => {
+                    result.push_str(&value[index..]);
+                    break;
+                }
+                _ => {}
+            }
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Mark every `$` of value loaded from structured file
+pub fn mark_value_references(value: &str) -> String {
+    value.replace('$', &REFERENCE_MARK.to_string())
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn parse_parts(value: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = value;
+
+    while let Some(index) = rest.find(REFERENCE_MARK) {
+        text.push_str(&rest[..index]);
+        rest = &rest[index + REFERENCE_MARK.len_utf8()..];
+
+        let reference = if let Some(block) = rest.strip_prefix('{') {
+            block.find('}').and_then(|end| {
+                let (name, default) = match block[..end].split_once(":-") {
+                    Some((name, default)) => (name, Some(default.to_string())),
+                    None => (&block[..end], None),
+                };
+                (!name.is_empty() && name.chars().all(is_name_char)).then(|| {
+                    (
+                        Part::Reference {
+                            name: name.to_string(),
+                            default,
+                        },
+                        end + 2,
+                    )
+                })
+            })
+        } else {
+            let end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            (end > 0).then(|| {
+                (
+                    Part::Reference {
+                        name: rest[..end].to_string(),
+                        default: None,
+                    },
+                    end,
+                )
+            })
+        };
+
+        match reference {
+            Some((reference, length)) => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(reference);
+                rest = &rest[length..];
+            }
+            None => text.push('$'),
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    parts
+}
+
+/// Resolve references to earlier entries of env file.
+///
+/// Like in dotenv files entries are resolved in file order, so references
+/// to entries defined later are reported as errors instead of getting outer value.
+/// Entry which refers to itself gets value from outer environment,
+/// such references and references to undefined names are resolved later
+/// by `expand_references`.
+pub fn expand_file_references(
+    environment: &Environment<String>,
+) -> DevrcResult<Environment<String>> {
+    let mut checked = HashSet::new();
+    for key in environment.keys() {
+        check_circular_references(key, environment, &mut checked, &mut Vec::new())?;
+    }
+
+    let mut expanded = Environment::new();
+    for (key, value) in environment {
+        let mut result = String::new();
+        for part in parse_parts(value) {
+            match &part {
+                Part::Reference { name, default }
+                    if name != key && environment.contains_key(name) =>
+                {
+                    let value =
+                        expanded
+                            .get(name)
+                            .ok_or_else(|| DevrcError::EnvFileLaterReference {
+                                name: key.clone(),
+                                reference: name.clone(),
+                            })?;
+                    match default {
+                        Some(default) if value.is_empty() => result.push_str(default),
+                        _ => result.push_str(value),
+                    }
+                }
+                _ => result.push_str(&part.to_marked()),
+            }
+        }
+        expanded.insert(key.clone(), result);
+    }
+    Ok(expanded)
+}
+
+fn check_circular_references(
+    key: &str,
+    environment: &Environment<String>,
+    checked: &mut HashSet<String>,
+    stack: &mut Vec<String>,
+) -> DevrcResult<()> {
+    if checked.contains(key) {
+        return Ok(());
+    }
+    if let Some(position) = stack.iter().position(|item| item == key) {
+        let mut chain = stack[position..].to_vec();
+        chain.push(key.to_string());
+        return Err(DevrcError::CircularVariables(chain));
+    }
+
+    stack.push(key.to_string());
+    for part in parse_parts(&environment[key]) {
+        if let Part::Reference { name, .. } = part {
+            if name != key && environment.contains_key(&name) {
+                check_circular_references(&name, environment, checked, stack)?;
+            }
+        }
+    }
+    stack.pop();
+
+    checked.insert(key.to_string());
+    Ok(())
+}
+
+/// Check if value has references which are not resolved yet
+pub fn has_references(value: &str) -> bool {
+    value.contains(REFERENCE_MARK)
+}
+
+/// Resolve references using given lookup, `${NAME:-default}` gives default
+/// if value is undefined or empty and undefined names are replaced by empty string
+pub fn expand_references<F>(value: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut result = String::new();
+    for part in parse_parts(value) {
+        match part {
+            Part::Text(text) => result.push_str(&text),
+            Part::Reference { name, default } => match (lookup(&name), default) {
+                (Some(value), _) if !value.is_empty() => result.push_str(&value),
+                (_, Some(default)) => result.push_str(&default),
+                (value, None) => result.push_str(&value.unwrap_or_default()),
+            },
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env_file::read_env_from_string;
+
+    fn parse(content: &str) -> DevrcResult<Environment<String>> {
+        expand_file_references(&read_env_from_string(&mark_references(content))?)
+    }
+
+    #[test]
+    fn test_expand_file_references() {
+        let environment = parse(
+            r#"
+# cost is $5, don't expand comments
+HOST=localhost
+NAME_1=api
+URL="http://${HOST}:${PORT:-8080}/$NAME_1"
+LITERAL='${HOST}'
+ESCAPED=\$HOST
+PATH=${PATH}:/opt/bin
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(environment["URL"], "http://localhost:\u{1}{PORT:-8080}/api");
+        assert_eq!(environment["LITERAL"], "${HOST}");
+        assert_eq!(environment["ESCAPED"], "$HOST");
+        assert_eq!(environment["PATH"], "\u{1}{PATH}:/opt/bin");
+
+        let lookup = |name: &str| match name {
+            "PATH" => Some("/usr/bin".to_string()),
+            "PORT" => Some("".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            expand_references(&environment["URL"], lookup),
+            "http://localhost:8080/api"
+        );
+        assert_eq!(
+            expand_references(&environment["PATH"], lookup),
+            "/usr/bin:/opt/bin"
+        );
+        assert_eq!(expand_references("\u{1}{UNDEFINED}-", lookup), "-");
+    }
+
+    #[test]
+    fn test_expand_later_references() {
+        match parse("URL=http://${HOST}/\nHOST=localhost\n") {
+            Err(DevrcError::EnvFileLaterReference { name, reference }) => {
+                assert_eq!((name.as_str(), reference.as_str()), ("URL", "HOST"))
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_expand_circular_references() {
+        match parse("A=${B}\nB=x${C}\nC=$A\n") {
+            Err(DevrcError::CircularVariables(names)) => {
+                assert_eq!(names, vec!["A", "B", "C", "A"])
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
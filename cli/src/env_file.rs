@@ -1,5 +1,6 @@
 use std::{convert::TryFrom, env, fs, path::PathBuf};
 
+use indexmap::IndexSet;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::Deserialize;
 use sha256::digest;
@@ -7,17 +8,66 @@ use url::Url;
 
 use crate::{
     de::deserialize_some,
+    env_expand::{expand_file_references, mark_references, mark_value_references},
     environment::Environment,
     errors::{DevrcError, DevrcResult},
     loader::LoadingConfig,
+    origin::Origin,
     resolver::{Location, PathResolve},
     utils,
+    variables::ValueFormat,
 };
 
 pub(crate) fn get_default_skip_on_error() -> bool {
     false
 }
 
+/// Syntax of env file
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvFileFormat {
+    #[default]
+    Dotenv,
+    Json,
+    Yaml,
+    /// Detect format by file extension, dotenv is used for unknown extensions
+    Auto,
+}
+
+fn get_default_separator() -> String {
+    "_".to_string()
+}
+
+/// Options of env file parsing
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct EnvFileParsing {
+    #[serde(default)]
+    pub format: EnvFileFormat,
+
+    /// Separator of nested keys of JSON and YAML files
+    #[serde(default = "get_default_separator")]
+    pub separator: String,
+
+    /// Expand `${NAME}` and `${NAME:-default}` references in values
+    #[serde(default)]
+    pub expand: bool,
+
+    /// Prefix added to names of loaded variables
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+impl Default for EnvFileParsing {
+    fn default() -> Self {
+        Self {
+            format: EnvFileFormat::default(),
+            separator: get_default_separator(),
+            expand: false,
+            prefix: None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct LocalFileImport {
     pub file: PathBuf,
@@ -30,6 +80,13 @@ pub struct LocalFileImport {
 
     #[serde(default, deserialize_with = "deserialize_some")]
     pub checksum: Option<String>,
+
+    /// Mask loaded values in output
+    #[serde(default)]
+    pub secret: bool,
+
+    #[serde(flatten)]
+    pub parsing: EnvFileParsing,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -43,6 +100,13 @@ pub struct UrlImport {
 
     #[serde(default)]
     pub headers: indexmap::IndexMap<String, String>,
+
+    /// Mask loaded values in output
+    #[serde(default)]
+    pub secret: bool,
+
+    #[serde(flatten)]
+    pub parsing: EnvFileParsing,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -56,6 +120,45 @@ pub enum EnvFilesInclude {
     List(Vec<EnvFilesInclude>),
 }
 
+/// Env files of task or subtask call which are loaded on execution
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(from = "EnvFilesInclude")]
+pub struct EnvFiles {
+    pub include: EnvFilesInclude,
+
+    /// Location of Devrcfile where files are defined
+    location: Location,
+
+    loading_config: LoadingConfig,
+
+    pub origin: Origin,
+}
+
+impl From<EnvFilesInclude> for EnvFiles {
+    fn from(include: EnvFilesInclude) -> Self {
+        Self {
+            include,
+            ..Default::default()
+        }
+    }
+}
+
+impl EnvFiles {
+    pub fn setup_loading(&mut self, location: &Location, config: &LoadingConfig) {
+        self.location = location.clone();
+        self.loading_config = config.clone();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self.include, EnvFilesInclude::Empty)
+    }
+
+    pub fn load(&self) -> DevrcResult<LoadedEnvironment> {
+        self.include
+            .load(self.location.clone(), self.loading_config.clone())
+    }
+}
+
 impl From<&PathBuf> for LocalFileImport {
     fn from(source: &PathBuf) -> Self {
         Self {
@@ -65,23 +168,58 @@ impl From<&PathBuf> for LocalFileImport {
     }
 }
 
+/// Environment variables loaded from env files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadedEnvironment {
+    pub environment: Environment<String>,
+
+    /// Values loaded from files marked as `secret`
+    pub secrets: IndexSet<String>,
+}
+
+impl LoadedEnvironment {
+    fn new(environment: Environment<String>, secret: bool) -> Self {
+        let secrets = if secret {
+            environment.values().cloned().collect()
+        } else {
+            IndexSet::new()
+        };
+        Self {
+            environment,
+            secrets,
+        }
+    }
+
+    pub fn extend(&mut self, other: LoadedEnvironment) {
+        self.environment.extend(other.environment);
+        self.secrets.extend(other.secrets);
+    }
+}
+
 impl EnvFilesInclude {
     pub fn load(
         &self,
         location: Location,
         config: LoadingConfig,
-    ) -> DevrcResult<Environment<String>> {
+    ) -> DevrcResult<LoadedEnvironment> {
         match self {
             EnvFilesInclude::Empty => Ok(Default::default()),
-            EnvFilesInclude::Simple(path) => LocalFileImport::from(path).load(location, config),
-            EnvFilesInclude::File(file_include) => file_include.load(location, config),
-            EnvFilesInclude::Url(remote_file) => remote_file.load(location, config),
+            EnvFilesInclude::Simple(path) => Ok(LoadedEnvironment::new(
+                LocalFileImport::from(path).load(location, config)?,
+                false,
+            )),
+            EnvFilesInclude::File(file_include) => Ok(LoadedEnvironment::new(
+                file_include.load(location, config)?,
+                file_include.secret,
+            )),
+            EnvFilesInclude::Url(remote_file) => Ok(LoadedEnvironment::new(
+                remote_file.load(location, config)?,
+                remote_file.secret,
+            )),
             EnvFilesInclude::List(list) => {
-                let mut env: Environment<String> = Default::default();
+                let mut env = LoadedEnvironment::default();
                 for include in list {
-                    for (key, value) in include.load(location.clone(), config.clone())? {
-                        env.insert(key, value);
-                    }
+                    env.extend(include.load(location.clone(), config.clone())?);
                 }
 
                 Ok(env)
@@ -106,22 +244,135 @@ pub fn read_env_from_string(input: &str) -> DevrcResult<Environment<String>> {
     Ok(environment)
 }
 
+impl EnvFileParsing {
+    /// Parse env file, resolve references between its entries if `expand`
+    /// is enabled and add `prefix` to names
+    pub fn parse(&self, name: &str, content: &str) -> DevrcResult<Environment<String>> {
+        let format = match self.format {
+            EnvFileFormat::Auto => match ValueFormat::from_extension(name) {
+                Some(ValueFormat::Json) => EnvFileFormat::Json,
+                Some(ValueFormat::Yaml) => EnvFileFormat::Yaml,
+                _ => EnvFileFormat::Dotenv,
+            },
+            format => format,
+        };
+
+        let environment = match format {
+            EnvFileFormat::Dotenv if self.expand => {
+                read_env_from_string(&mark_references(content))?
+            }
+            EnvFileFormat::Dotenv => read_env_from_string(content)?,
+            EnvFileFormat::Json => self.read_structured(name, content, ValueFormat::Json)?,
+            EnvFileFormat::Yaml | EnvFileFormat::Auto => {
+                self.read_structured(name, content, ValueFormat::Yaml)?
+            }
+        };
+
+        let environment = if self.expand {
+            expand_file_references(&environment)?
+        } else {
+            environment
+        };
+
+        Ok(match &self.prefix {
+            Some(prefix) => environment
+                .into_iter()
+                .map(|(key, value)| (format!("{}{}", prefix, key), value))
+                .collect(),
+            None => environment,
+        })
+    }
+
+    /// Read JSON or YAML mapping, nested keys are joined by `separator`
+    fn read_structured(
+        &self,
+        name: &str,
+        content: &str,
+        format: ValueFormat,
+    ) -> DevrcResult<Environment<String>> {
+        let error = |reason: String| DevrcError::EnvfileParseError {
+            name: name.to_string(),
+            reason,
+        };
+
+        let items = match format.parse(name, content) {
+            Ok(Some(tera::Value::Object(items))) => items,
+            Ok(Some(tera::Value::Null)) => return Ok(Environment::default()),
+            Ok(_) => return Err(error("env file must contain a mapping".to_string())),
+            Err(DevrcError::VariableParseError { reason, .. }) => return Err(error(reason)),
+            Err(other) => return Err(other),
+        };
+
+        let mut environment = Environment::default();
+        for (key, value) in items {
+            self.flatten(key, &value, &mut environment);
+        }
+        Ok(environment)
+    }
+
+    fn flatten(&self, name: String, value: &tera::Value, environment: &mut Environment<String>) {
+        let value = match value {
+            tera::Value::Object(items) => {
+                for (key, item) in items {
+                    self.flatten(
+                        format!("{}{}{}", name, self.separator, key),
+                        item,
+                        environment,
+                    );
+                }
+                return;
+            }
+            tera::Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.flatten(
+                        format!("{}{}{}", name, self.separator, index),
+                        item,
+                        environment,
+                    );
+                }
+                return;
+            }
+            tera::Value::Null => String::new(),
+            tera::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+
+        // References are marked in dotenv files before parsing
+        let value = if self.expand {
+            mark_value_references(&value)
+        } else {
+            value
+        };
+        environment.insert(name, value);
+    }
+}
+
 impl Loader for LocalFileImport {
     fn load(&self, location: Location, config: LoadingConfig) -> DevrcResult<Environment<String>> {
         let environment = if self.ignore_errors {
             match self.get_content(location, config) {
-                Ok(content) => read_env_from_string(&content).unwrap_or_default(),
+                Ok(content) => self
+                    .parsing
+                    .parse(&self.file.display().to_string(), &content)
+                    .unwrap_or_default(),
                 Err(_error) => Environment::default(),
             }
         } else {
-            read_env_from_string(&self.get_content(location, config)?)?
+            self.parsing.parse(
+                &self.file.display().to_string(),
+                &self.get_content(location, config)?,
+            )?
         };
         Ok(environment)
     }
 }
 
 impl LocalFileImport {
-    fn get_content(&self, location: Location, config: LoadingConfig) -> DevrcResult<String> {
+    pub(crate) fn get_content(
+        &self,
+        location: Location,
+        config: LoadingConfig,
+    ) -> DevrcResult<String> {
         let loading_location = self.get_loading_location(location, &config)?;
 
         config.log_level.debug(
@@ -133,7 +384,8 @@ impl LocalFileImport {
             Location::LocalFile(path) => fs::read_to_string(path).map_err(DevrcError::IoError),
             Location::Remote { url, auth } => {
                 if let Some(cache_ttl) = config.cache_ttl {
-                    if let Some(content) = crate::cache::load(&url, &config, None, &cache_ttl) {
+                    if let Some(content) = crate::cache::load(&url, &[], &config, None, &cache_ttl)
+                    {
                         config.log_level.debug(
                             &format!("\n==> Loading ENV URL CACHE: `{}` ...", &url),
                             &config.designer.banner(),
@@ -259,11 +511,12 @@ impl Loader for UrlImport {
     fn load(&self, location: Location, config: LoadingConfig) -> DevrcResult<Environment<String>> {
         let environment = if self.ignore_errors {
             match self.get_content(location, config) {
-                Ok(content) => read_env_from_string(&content).unwrap_or_default(),
+                Ok(content) => self.parsing.parse(&self.url, &content).unwrap_or_default(),
                 Err(_) => Environment::default(),
             }
         } else {
-            read_env_from_string(&self.get_content(location, config)?)?
+            self.parsing
+                .parse(&self.url, &self.get_content(location, config)?)?
         };
         Ok(environment)
     }
@@ -276,7 +529,7 @@ impl UrlImport {
 
         if let Some(cache_ttl) = config.cache_ttl {
             if let Some(content) =
-                crate::cache::load(&parsed_url, &config, Some(&self.checksum), &cache_ttl)
+                crate::cache::load(&parsed_url, &[], &config, Some(&self.checksum), &cache_ttl)
             {
                 config.log_level.debug(
                     &format!("\n==> Loading ENV URL CACHE: `{}` ...", &parsed_url),
@@ -306,7 +559,7 @@ impl UrlImport {
                 }
 
                 if config.cache_ttl.is_some() {
-                    crate::cache::save(&parsed_url, &content)?;
+                    crate::cache::save(&parsed_url, &[], &content)?;
                 }
 
                 Ok(content)
@@ -337,3 +590,127 @@ impl UrlImport {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::scope::{child_scope, Scope};
+
+    #[test]
+    fn test_parse_env_file() {
+        let content = "HOST=db\nURL=${HOST}:${DEVRC_TEST_PORT:-5432}\n";
+        let mut parsing = EnvFileParsing {
+            prefix: Some("DB_".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parsing.parse(".env", content).unwrap(),
+            indexmap::indexmap! {
+                "DB_HOST".to_string() => "db".to_string(),
+                "DB_URL".to_string() => "db:".to_string(),
+            }
+        );
+
+        parsing.expand = true;
+        let environment = parsing.parse(".env", content).unwrap();
+        assert_eq!(
+            environment.keys().collect::<Vec<_>>(),
+            vec!["DB_HOST", "DB_URL"]
+        );
+
+        let mut scope = Scope::default();
+        scope
+            .process_loaded_env(
+                &LoadedEnvironment {
+                    environment,
+                    secrets: IndexSet::new(),
+                },
+                &Origin::None,
+            )
+            .unwrap();
+        assert_eq!(scope.get_env_var("DB_URL"), Some(&"db:5432".to_string()));
+    }
+
+    #[test]
+    fn test_parse_structured_env_file() {
+        let parsing: EnvFileParsing = serde_yaml::from_str("format: auto").unwrap();
+        let content = r#"{"db": {"host": "db", "port": 5432, "replica": null}, "hosts": ["a", "b"], "debug": true}"#;
+
+        assert_eq!(
+            parsing.parse("outputs.json?ref=main", content).unwrap(),
+            indexmap::indexmap! {
+                "db_host".to_string() => "db".to_string(),
+                "db_port".to_string() => "5432".to_string(),
+                "db_replica".to_string() => "".to_string(),
+                "hosts_0".to_string() => "a".to_string(),
+                "hosts_1".to_string() => "b".to_string(),
+                "debug".to_string() => "true".to_string(),
+            }
+        );
+
+        let parsing: EnvFileParsing =
+            serde_yaml::from_str("{format: yaml, separator: __, expand: true}").unwrap();
+        let content = "db:\n  host: db\n  url: postgres://${db__host}\n";
+        assert_eq!(
+            parsing.parse("secrets", content).unwrap(),
+            indexmap::indexmap! {
+                "db__host".to_string() => "db".to_string(),
+                "db__url".to_string() => "postgres://db".to_string(),
+            }
+        );
+
+        assert!(parsing.parse("secrets", "- item").is_err());
+
+        // Unknown extension is parsed as dotenv
+        let parsing: EnvFileParsing = serde_yaml::from_str("format: auto").unwrap();
+        assert_eq!(
+            parsing.parse("staging.env", "A=1").unwrap(),
+            indexmap::indexmap! {"A".to_string() => "1".to_string()}
+        );
+    }
+
+    #[test]
+    fn test_load_task_env_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("common.env"), "REGION=eu\nTOKEN=common\n").unwrap();
+        fs::write(dir.path().join("staging.env"), "TOKEN=staging\n").unwrap();
+
+        let mut env_files: EnvFiles = serde_yaml::from_str(
+            r#"
+- ./common.env
+- file: ./staging.env
+  secret: true
+- file: ./missing.env
+  ignore_errors: true
+"#,
+        )
+        .unwrap();
+        env_files.setup_loading(
+            &Location::LocalFile(dir.path().to_path_buf()),
+            &LoadingConfig::default(),
+        );
+
+        let loaded = env_files.load().unwrap();
+        assert_eq!(
+            loaded.environment,
+            indexmap::indexmap! {
+                "REGION".to_string() => "eu".to_string(),
+                "TOKEN".to_string() => "staging".to_string(),
+            }
+        );
+        assert_eq!(loaded.secrets.iter().collect::<Vec<_>>(), vec!["staging"]);
+
+        let parent = Rc::new(RefCell::new(Scope::default()));
+        let mut scope = child_scope(Rc::clone(&parent), "deploy");
+        scope
+            .process_loaded_env(&loaded, &env_files.origin)
+            .unwrap();
+
+        assert_eq!(scope.get_env_var("TOKEN"), Some(&"staging".to_string()));
+        assert_eq!(parent.borrow().get_env_var("TOKEN"), None);
+        assert_eq!(scope.mask("TOKEN=staging"), "TOKEN=***");
+    }
+}
@@ -1,19 +1,107 @@
 use serde::Deserialize;
 
-use crate::{errors::DevrcResult, evaluate::Evaluatable, scope::Scope};
+use crate::{
+    de::deserialize_string_or_list, errors::DevrcResult, evaluate::Evaluatable, origin::Origin,
+    scope::Scope, template::render_string,
+};
 
 pub type Environment<T> = indexmap::IndexMap<T, String>;
 
+/// Separator of paths lists such as `PATH`
+#[cfg(windows)]
+pub const PATH_SEPARATOR: &str = ";";
+#[cfg(not(windows))]
+pub const PATH_SEPARATOR: &str = ":";
+
+/// Value of environment variable definition
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum EnvValue {
+    /// `null` removes inherited variable
+    Unset,
+    String(String),
+    Modify(PathsModification),
+}
+
+/// Paths which are added to current value of variable
+#[derive(Debug, Deserialize, Clone, Eq, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PathsModification {
+    #[serde(default, deserialize_with = "deserialize_string_or_list")]
+    pub prepend: Vec<String>,
+
+    #[serde(default, deserialize_with = "deserialize_string_or_list")]
+    pub append: Vec<String>,
+
+    /// Platform paths separator is used by default
+    pub separator: Option<String>,
+}
+
+impl From<String> for EnvValue {
+    fn from(value: String) -> Self {
+        EnvValue::String(value)
+    }
+}
+
+impl EnvValue {
+    /// Get templates of value
+    pub fn get_templates(&self) -> Vec<&str> {
+        match self {
+            EnvValue::Unset => vec![],
+            EnvValue::String(value) => vec![value.as_str()],
+            EnvValue::Modify(modification) => modification
+                .prepend
+                .iter()
+                .chain(modification.append.iter())
+                .map(|value| value.as_str())
+                .collect(),
+        }
+    }
+
+    /// Render value, `None` means that variable is removed.
+    /// Paths are added to `current` value.
+    pub fn evaluate(
+        &self,
+        name: &str,
+        scope: &Scope,
+        current: Option<String>,
+    ) -> DevrcResult<Option<String>> {
+        match self {
+            EnvValue::Unset => Ok(None),
+            EnvValue::String(value) => Ok(Some(render_string(name, value, scope)?)),
+            EnvValue::Modify(modification) => {
+                let mut paths = Vec::new();
+                for path in &modification.prepend {
+                    paths.push(render_string(name, path, scope)?);
+                }
+                paths.extend(current.filter(|value| !value.is_empty()));
+                for path in &modification.append {
+                    paths.push(render_string(name, path, scope)?);
+                }
+
+                let separator = modification.separator.as_deref().unwrap_or(PATH_SEPARATOR);
+                Ok(Some(paths.join(separator)))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
 pub struct RawEnvironment<T> {
     #[serde(flatten)]
     pub vars: indexmap::IndexMap<String, T>,
+
+    #[serde(skip)]
+    pub origin: Origin,
 }
 
 impl<T> Default for RawEnvironment<T> {
     fn default() -> Self {
         let vars = indexmap::IndexMap::new();
-        Self { vars }
+        Self {
+            vars,
+            origin: Origin::None,
+        }
     }
 }
 
@@ -35,9 +123,11 @@ where
         }
         Ok(vars)
     }
+}
 
-    pub fn add(&mut self, name: &str, value: T) {
-        self.vars.insert(name.to_owned(), value);
+impl<T> RawEnvironment<T> {
+    pub fn add<V: Into<T>>(&mut self, name: &str, value: V) {
+        self.vars.insert(name.to_owned(), value.into());
     }
 }
 
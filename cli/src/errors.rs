@@ -45,16 +45,45 @@ pub enum DevrcError {
     InvalidParams,
     InvalidVariableName,
     InvalidVariableModifier,
+    VariableParseError {
+        name: String,
+        reason: String,
+    },
+    ComputableVariableError {
+        name: String,
+        inner: Box<DevrcError>,
+    },
     InvalidIncludeUrl(String),
     TaskArgumentsParsingError,
     OverlappingParameters,
     NotEnouthArguments,
+    UnexpectedArguments(Vec<String>),
+    UnknownArgument(String),
+    TemplatesCheckFailed(usize),
+    VariableNotFound(String),
+    ReservedVariableName(String),
+    CircularVariables(Vec<String>),
+    EnvFileLaterReference {
+        name: String,
+        reference: String,
+    },
+    InvalidArgumentValue {
+        name: String,
+        value: String,
+        reason: String,
+    },
     InvalidInterpreter,
     NestingLevelExceed,
     RuntimeError,
+    NotInteractive,
+    TaskNotConfirmed,
     EnvfileImportError {
         location: Location,
     },
+    EnvfileParseError {
+        name: String,
+        reason: String,
+    },
     EnvfileUrlImportStatusError {
         url: String,
         status: StatusCode,
@@ -114,6 +143,65 @@ impl Display for DevrcError {
             DevrcError::InvalidIncludeUrl(url) => {
                 write!(f, "Invalid include url {:}", &url)?;
             }
+            DevrcError::InvalidArgumentValue {
+                name,
+                value,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "Invalid value `{:}` of parameter `{:}`: {:}",
+                    value, name, reason
+                )?;
+            }
+            DevrcError::UnexpectedArguments(values) => {
+                write!(f, "Unexpected task arguments `{:}`", values.join(" "))?;
+            }
+            DevrcError::UnknownArgument(name) => {
+                write!(f, "Task has no parameter `{:}`", name)?;
+            }
+            DevrcError::VariableParseError { name, reason } => {
+                write!(
+                    f,
+                    "Failed to parse value of variable `{:}`: {:}",
+                    name, reason
+                )?;
+            }
+            DevrcError::EnvfileParseError { name, reason } => {
+                write!(f, "Failed to parse env file `{:}`: {:}", name, reason)?;
+            }
+            DevrcError::ComputableVariableError { name, inner } => {
+                write!(f, "Failed to compute variable `{:}`: {:?}", name, inner)?;
+            }
+            DevrcError::TemplatesCheckFailed(count) => {
+                write!(f, "Templates check failed: {:} issues found", count)?;
+            }
+            DevrcError::VariableNotFound(name) => {
+                write!(f, "Variable `{:}` not found", name)?;
+            }
+            DevrcError::ReservedVariableName(name) => {
+                write!(f, "Variable name `{:}` is reserved", name)?;
+            }
+            DevrcError::CircularVariables(names) => {
+                write!(
+                    f,
+                    "Circular reference between variables: {:}",
+                    names.join(" -> ")
+                )?;
+            }
+            DevrcError::EnvFileLaterReference { name, reference } => {
+                write!(
+                    f,
+                    "Env file entry `{:}` refers to `{:}` which is defined later in the file, only earlier entries can be referenced",
+                    name, reference
+                )?;
+            }
+            DevrcError::NotInteractive => {
+                write!(f, "User input required but stdin is not interactive")?;
+            }
+            DevrcError::TaskNotConfirmed => {
+                write!(f, "Task execution is not confirmed")?;
+            }
             _ => {}
         }
         Ok(())
@@ -1,12 +1,22 @@
-use crate::errors::DevrcResult;
+use crate::{config::Config, errors::DevrcResult};
 
-use std::process::Command;
+use std::{env, process::Command};
+
+use indexmap::IndexSet;
 
 pub trait CommandExt {
     fn export_environment(
         &mut self,
         environment: &indexmap::IndexMap<String, String>,
     ) -> DevrcResult<()>;
+
+    fn setup_environment(
+        &mut self,
+        environment: &indexmap::IndexMap<String, String>,
+        unset: &IndexSet<String>,
+        config: &Config,
+        process_environment: &indexmap::IndexMap<String, String>,
+    ) -> DevrcResult<()>;
 }
 
 impl CommandExt for Command {
@@ -20,6 +30,134 @@ impl CommandExt for Command {
 
         Ok(())
     }
+
+    fn setup_environment(
+        &mut self,
+        environment: &indexmap::IndexMap<String, String>,
+        unset: &IndexSet<String>,
+        config: &Config,
+        process_environment: &indexmap::IndexMap<String, String>,
+    ) -> DevrcResult<()> {
+        if config.env_clean {
+            self.env_clear();
+        }
+
+        for key in unset {
+            self.env_remove(key);
+        }
+
+        self.export_environment(&get_execution_environment(
+            environment,
+            unset,
+            config,
+            process_environment,
+        ))
+    }
+}
+
+/// Get environment of devrc process, variables with non unicode values are skipped
+pub fn get_process_environment() -> indexmap::IndexMap<String, String> {
+    env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
+
+/// Get environment passed to child process.
+///
+/// If `env_clean` is enabled only variables from `env_passthrough`
+/// are taken from `process_environment`. Variables from `unset`
+/// are never passed through.
+pub fn get_execution_environment(
+    environment: &indexmap::IndexMap<String, String>,
+    unset: &IndexSet<String>,
+    config: &Config,
+    process_environment: &indexmap::IndexMap<String, String>,
+) -> indexmap::IndexMap<String, String> {
+    let mut result = indexmap::IndexMap::new();
+
+    if config.env_clean {
+        for name in &config.env_passthrough {
+            if unset.contains(name) {
+                continue;
+            }
+            if let Some(value) = process_environment.get(name) {
+                result.insert(name.to_owned(), value.to_owned());
+            }
+        }
+    }
+
+    for (key, value) in environment {
+        result.insert(key.to_owned(), value.to_owned());
+    }
+
+    result
 }
 
 pub trait Executor {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execution_environment() {
+        let process_environment = indexmap::indexmap! {
+            "DEVRC_TEST_PASSTHROUGH".to_string() => "passthrough".to_string(),
+            "DEVRC_TEST_HIDDEN".to_string() => "hidden".to_string(),
+        };
+
+        let mut environment = indexmap::IndexMap::new();
+        environment.insert("LOCAL".to_string(), "local".to_string());
+
+        let config = Config {
+            env_clean: true,
+            env_passthrough: vec![
+                "DEVRC_TEST_PASSTHROUGH".to_string(),
+                "DEVRC_TEST_UNDEFINED".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_execution_environment(
+                &environment,
+                &IndexSet::new(),
+                &config,
+                &process_environment
+            ),
+            indexmap::indexmap! {
+                "DEVRC_TEST_PASSTHROUGH".to_string() => "passthrough".to_string(),
+                "LOCAL".to_string() => "local".to_string(),
+            }
+        );
+
+        let config = Config {
+            env_passthrough: vec!["DEVRC_TEST_PASSTHROUGH".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_execution_environment(
+                &environment,
+                &IndexSet::new(),
+                &config,
+                &process_environment
+            ),
+            environment
+        );
+
+        let config = Config {
+            env_clean: true,
+            env_passthrough: vec!["DEVRC_TEST_PASSTHROUGH".to_string()],
+            ..Default::default()
+        };
+        let unset: IndexSet<String> = vec!["DEVRC_TEST_PASSTHROUGH".to_string()]
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            get_execution_environment(&environment, &unset, &config, &process_environment),
+            environment
+        );
+    }
+}
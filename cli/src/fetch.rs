@@ -0,0 +1,59 @@
+use std::convert::TryFrom;
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    StatusCode,
+};
+use sha256::digest;
+use url::Url;
+
+use crate::errors::{DevrcError, DevrcResult};
+
+/// Get content of url, content is compared with checksum if it's given
+pub fn fetch_url(
+    url: &Url,
+    headers: &[(String, String)],
+    checksum: Option<&str>,
+) -> DevrcResult<String> {
+    let mut headers_map = HeaderMap::new();
+    for (key, value) in headers {
+        let error = || DevrcError::UrlImportHeadersError {
+            name: key.clone(),
+            value: value.clone(),
+        };
+        headers_map.insert(
+            HeaderName::try_from(key.clone()).map_err(|_| error())?,
+            HeaderValue::try_from(value.clone()).map_err(|_| error())?,
+        );
+    }
+
+    let client = reqwest::blocking::Client::new();
+
+    match client.get(url.as_str()).headers(headers_map).send() {
+        Ok(response) if response.status() == StatusCode::OK => {
+            let content = response.text().map_err(|_| DevrcError::RuntimeError)?;
+
+            if let Some(control_checksum) = checksum {
+                let content_checksum = digest(content.as_str());
+
+                if control_checksum != content_checksum {
+                    return Err(DevrcError::UrlImportChecksumError {
+                        url: url.as_str().to_string(),
+                        control_checksum: control_checksum.to_string(),
+                        content_checksum,
+                    });
+                }
+            }
+
+            Ok(content)
+        }
+        Ok(response) => Err(DevrcError::UrlImportStatusError {
+            url: url.as_str().to_string(),
+            status: response.status(),
+        }),
+        Err(error) => Err(DevrcError::UrlImportRequestError {
+            url: url.as_str().to_string(),
+            inner: error,
+        }),
+    }
+}
@@ -0,0 +1,479 @@
+use std::{fmt::Write, str::FromStr};
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::{
+    devrcfile::Devrcfile,
+    errors::{DevrcError, DevrcResult},
+    registry::Registry,
+    resolver::Location,
+    tasks::extract_name_and_params,
+};
+
+/// Palette used to distinguish files tasks are loaded from
+const FILE_COLORS: &[&str] = &[
+    "#a6cee3", "#b2df8a", "#fb9a99", "#fdbf6f", "#cab2d6", "#ffff99", "#1f78b4", "#33a02c",
+];
+
+const HOOKS: &[&str] = &["before_script", "before_task", "after_task", "after_script"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = DevrcError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(DevrcError::InvalidArgument),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Dependency,
+    Subtask,
+    Hook,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+/// Dependencies and subtasks graph of loaded tasks
+#[derive(Debug, Clone, Default)]
+pub struct TaskGraph {
+    /// Task name and index of the file it is defined in
+    pub nodes: IndexMap<String, Option<usize>>,
+    pub edges: Vec<Edge>,
+    pub files: Vec<Location>,
+}
+
+impl TaskGraph {
+    /// Build graph for given tasks or for all tasks if `names` is empty
+    pub fn build(
+        devrcfile: &Devrcfile,
+        registry: &Registry,
+        names: &[String],
+        with_hooks: bool,
+    ) -> DevrcResult<Self> {
+        let mut graph = TaskGraph::default();
+        let origins = graph.collect_origins(registry)?;
+
+        let roots: Vec<String> = if names.is_empty() {
+            devrcfile
+                .get_tasks_docs()
+                .map(|(name, _)| name.to_string())
+                .filter(|name| !name.starts_with('_'))
+                .collect()
+        } else {
+            names.to_vec()
+        };
+
+        for name in &roots {
+            devrcfile.find_task(name)?;
+            graph.visit(devrcfile, &origins, name)?;
+        }
+
+        if with_hooks {
+            let tasks: Vec<String> = graph.nodes.keys().cloned().collect();
+
+            for hook in HOOKS {
+                if devrcfile.find_task(hook).is_err() {
+                    continue;
+                }
+                graph.visit(devrcfile, &origins, hook)?;
+
+                let hook_edges = match *hook {
+                    "before_script" => roots
+                        .iter()
+                        .map(|task| (hook.to_string(), task.clone()))
+                        .collect::<Vec<_>>(),
+                    "after_script" => roots
+                        .iter()
+                        .map(|task| (task.clone(), hook.to_string()))
+                        .collect(),
+                    "before_task" => tasks
+                        .iter()
+                        .map(|task| (hook.to_string(), task.clone()))
+                        .collect(),
+                    _ => tasks
+                        .iter()
+                        .map(|task| (task.clone(), hook.to_string()))
+                        .collect(),
+                };
+
+                for (from, to) in hook_edges {
+                    graph.add_edge(&from, &to, EdgeKind::Hook);
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Find the file which defines each task. Later files override earlier ones
+    /// the same way `Devrcfile::add_raw_devrcfile` merges them.
+    fn collect_origins(&mut self, registry: &Registry) -> DevrcResult<IndexMap<String, usize>> {
+        let mut origins = IndexMap::new();
+
+        for file in &registry.files {
+            let mut names: Vec<String> = Vec::new();
+
+            for name in file.tasks.items.keys() {
+                let (name, _) = extract_name_and_params(name.to_string())?;
+                names.push(name);
+            }
+
+            for (hook, defined) in [
+                ("before_script", matches!(file.before_script, Some(Some(_)))),
+                ("after_script", matches!(file.after_script, Some(Some(_)))),
+                ("before_task", matches!(file.before_task, Some(Some(_)))),
+                ("after_task", matches!(file.after_task, Some(Some(_)))),
+            ] {
+                if defined {
+                    names.push(hook.to_string());
+                }
+            }
+
+            if names.is_empty() {
+                continue;
+            }
+
+            let index = self.files.len();
+            self.files.push(file.location.clone());
+
+            for name in names {
+                origins.insert(name, index);
+            }
+        }
+
+        Ok(origins)
+    }
+
+    fn visit(
+        &mut self,
+        devrcfile: &Devrcfile,
+        origins: &IndexMap<String, usize>,
+        name: &str,
+    ) -> DevrcResult<()> {
+        if self.nodes.contains_key(name) {
+            return Ok(());
+        }
+        self.nodes
+            .insert(name.to_string(), origins.get(name).copied());
+
+        let task = devrcfile.find_task(name)?;
+
+        if let Some(deps) = task.get_dependencies() {
+            for dependency in deps {
+                self.add_edge(name, dependency, EdgeKind::Dependency);
+                self.visit(devrcfile, origins, dependency)?;
+            }
+        }
+
+        if let Some(subtasks) = task.get_subtasks() {
+            for subtask in subtasks {
+                self.add_edge(name, &subtask.name, EdgeKind::Subtask);
+                self.visit(devrcfile, origins, &subtask.name)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, kind: EdgeKind) {
+        let edge = Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+        };
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    fn get_file_color(index: usize) -> &'static str {
+        FILE_COLORS[index % FILE_COLORS.len()]
+    }
+
+    fn get_file_name(&self, index: usize) -> String {
+        match &self.files[index] {
+            Location::None => "<none>".to_string(),
+            Location::StdIn => "<stdin>".to_string(),
+            location => location.to_string(),
+        }
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut output = String::new();
+
+        let _ = writeln!(output, "digraph devrc {{");
+        let _ = writeln!(output, "  rankdir=LR;");
+        let _ = writeln!(output, "  node [shape=box, style=\"rounded,filled\"];");
+
+        for index in 0..self.files.len() {
+            let _ = writeln!(
+                output,
+                "  // {} = {}",
+                Self::get_file_color(index),
+                self.get_file_name(index)
+            );
+        }
+
+        for (name, file) in &self.nodes {
+            let mut attributes = vec![format!("label={}", dot_quote(name))];
+            if let Some(index) = file {
+                attributes.push(format!(
+                    "fillcolor={}",
+                    dot_quote(Self::get_file_color(*index))
+                ));
+                attributes.push(format!(
+                    "tooltip={}",
+                    dot_quote(&self.get_file_name(*index))
+                ));
+            } else {
+                attributes.push("fillcolor=\"white\"".to_string());
+            }
+            if HOOKS.contains(&name.as_str()) {
+                attributes.push("shape=ellipse".to_string());
+            }
+            let _ = writeln!(output, "  {} [{}];", dot_quote(name), attributes.join(", "));
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Dependency => "[label=\"deps\"]",
+                EdgeKind::Subtask => "[label=\"subtask\", style=dashed]",
+                EdgeKind::Hook => "[style=dotted]",
+            };
+            let _ = writeln!(
+                output,
+                "  {} -> {} {};",
+                dot_quote(&edge.from),
+                dot_quote(&edge.to),
+                style
+            );
+        }
+
+        output.push('}');
+        output
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let mut output = String::new();
+        let ids: IndexMap<&String, String> = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(index, name)| (name, format!("task{}", index)))
+            .collect();
+
+        let _ = writeln!(output, "flowchart LR");
+
+        for name in self.nodes.keys() {
+            let label = mermaid_quote(name);
+            if HOOKS.contains(&name.as_str()) {
+                let _ = writeln!(output, "  {}([\"{}\"])", ids[name], label);
+            } else {
+                let _ = writeln!(output, "  {}[\"{}\"]", ids[name], label);
+            }
+        }
+
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::Dependency => "-->|deps|",
+                EdgeKind::Subtask => "-.->|subtask|",
+                EdgeKind::Hook => "-.->",
+            };
+            let _ = writeln!(output, "  {} {} {}", ids[&edge.from], arrow, ids[&edge.to]);
+        }
+
+        let used_files: IndexSet<usize> = self.nodes.values().flatten().copied().collect();
+
+        for index in 0..self.files.len() {
+            if !used_files.contains(&index) {
+                continue;
+            }
+            let _ = writeln!(output, "  %% file{} = {}", index, self.get_file_name(index));
+            let _ = writeln!(
+                output,
+                "  classDef file{} fill:{}",
+                index,
+                Self::get_file_color(index)
+            );
+            let members: Vec<&str> = self
+                .nodes
+                .iter()
+                .filter(|(_, file)| **file == Some(index))
+                .map(|(name, _)| ids[name].as_str())
+                .collect();
+            let _ = writeln!(output, "  class {} file{}", members.join(","), index);
+        }
+
+        output.trim_end().to_string()
+    }
+}
+
+fn dot_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn mermaid_quote(value: &str) -> String {
+    value.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        loader::LoadingConfig,
+        raw::devrcfile::{Kind, RawDevrcfile},
+    };
+
+    fn load(files: &[(&str, &str)]) -> (Devrcfile, Registry) {
+        let mut devrcfile = Devrcfile::default();
+        let mut registry = Registry::default();
+
+        for (path, content) in files {
+            let file = RawDevrcfile::prepared_from_str(
+                content,
+                Location::LocalFile(PathBuf::from(path)),
+                Kind::Args,
+                LoadingConfig::default(),
+            )
+            .unwrap();
+            devrcfile
+                .add_raw_devrcfile(file.clone(), &Kind::Args)
+                .unwrap();
+            registry.add(file).unwrap();
+        }
+        (devrcfile, registry)
+    }
+
+    #[test]
+    fn test_graph_build() {
+        let (devrcfile, registry) = load(&[
+            (
+                "/project/Devrcfile",
+                r#"
+clean: echo clean
+build:
+  exec: echo build
+  deps: [clean]
+deploy target:
+  exec: echo deploy
+  deps: [build]
+  subtasks:
+    - name: notify
+before_task: echo before
+"#,
+            ),
+            ("/project/Devrcfile.local", "notify: echo notify"),
+        ]);
+
+        let graph =
+            TaskGraph::build(&devrcfile, &registry, &["deploy".to_string()], false).unwrap();
+
+        assert_eq!(
+            graph.nodes.keys().collect::<Vec<_>>(),
+            vec!["deploy", "build", "clean", "notify"]
+        );
+        assert_eq!(graph.nodes["deploy"], Some(0));
+        assert_eq!(graph.nodes["notify"], Some(1));
+        assert_eq!(
+            graph.edges,
+            vec![
+                Edge {
+                    from: "deploy".to_string(),
+                    to: "build".to_string(),
+                    kind: EdgeKind::Dependency
+                },
+                Edge {
+                    from: "build".to_string(),
+                    to: "clean".to_string(),
+                    kind: EdgeKind::Dependency
+                },
+                Edge {
+                    from: "deploy".to_string(),
+                    to: "notify".to_string(),
+                    kind: EdgeKind::Subtask
+                },
+            ]
+        );
+
+        let graph = TaskGraph::build(&devrcfile, &registry, &["build".to_string()], true).unwrap();
+        assert!(graph.nodes.contains_key("before_task"));
+        assert!(graph.edges.contains(&Edge {
+            from: "before_task".to_string(),
+            to: "clean".to_string(),
+            kind: EdgeKind::Hook
+        }));
+    }
+
+    #[test]
+    fn test_graph_unknown_task() {
+        let (devrcfile, registry) = load(&[("/project/Devrcfile", "build: echo build")]);
+
+        match TaskGraph::build(&devrcfile, &registry, &["unknown".to_string()], false) {
+            Err(DevrcError::TaskNotFound) => {}
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_graph_render() {
+        let (devrcfile, registry) = load(&[(
+            "/project/Devrcfile",
+            r#"
+clean: echo clean
+build:
+  exec: echo build
+  deps: [clean]
+"#,
+        )]);
+
+        let graph = TaskGraph::build(&devrcfile, &registry, &[], false).unwrap();
+
+        assert_eq!(
+            graph.render(GraphFormat::Dot),
+            r##"digraph devrc {
+  rankdir=LR;
+  node [shape=box, style="rounded,filled"];
+  // #a6cee3 = /project/Devrcfile
+  "clean" [label="clean", fillcolor="#a6cee3", tooltip="/project/Devrcfile"];
+  "build" [label="build", fillcolor="#a6cee3", tooltip="/project/Devrcfile"];
+  "build" -> "clean" [label="deps"];
+}"##
+        );
+
+        assert_eq!(
+            graph.render(GraphFormat::Mermaid),
+            r##"flowchart LR
+  task0["clean"]
+  task1["build"]
+  task1 -->|deps| task0
+  %% file0 = /project/Devrcfile
+  classDef file0 fill:#a6cee3
+  class task0,task1 file0"##
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    env,
+    io::{self, BufRead, Write},
+};
+
+use devrc_core::workshop::Designer;
+
+use crate::errors::{DevrcError, DevrcResult};
+
+/// Environment variable to answer `yes` to all confirmations
+pub const ASSUME_YES_ENV: &str = "DEVRC_ASSUME_YES";
+
+/// Check that user can answer prompts
+pub fn is_interactive() -> bool {
+    atty::is(atty::Stream::Stdin)
+}
+
+pub fn is_assume_yes_env() -> bool {
+    match env::var(ASSUME_YES_ENV) {
+        Ok(value) => is_positive_answer(&value) || value == "1" || value.to_lowercase() == "true",
+        Err(_) => false,
+    }
+}
+
+fn is_positive_answer(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Print message and read one line from stdin
+pub fn read_line(message: &str, designer: &Designer) -> DevrcResult<String> {
+    let mut stderr = io::stderr();
+    write!(
+        stderr,
+        "{}{}{}",
+        designer.prefix(),
+        message,
+        designer.suffix()
+    )?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Err(DevrcError::NotInteractive);
+    }
+    Ok(answer.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Print message and read one line from terminal without echoing it.
+///
+/// Fails if there is no terminal where echo can be disabled,
+/// terminal settings are restored on return and on `Ctrl-C`.
+pub fn read_hidden_line(message: &str, designer: &Designer) -> DevrcResult<String> {
+    rpassword::prompt_password(format!(
+        "{}{}{}",
+        designer.prefix(),
+        message,
+        designer.suffix()
+    ))
+    .map_err(|error| match error.kind() {
+        io::ErrorKind::UnexpectedEof => DevrcError::NotInteractive,
+        _ => DevrcError::IoError(error),
+    })
+}
+
+/// Ask user to confirm action
+pub fn confirm(message: &str, designer: &Designer) -> DevrcResult<bool> {
+    let answer = read_line(&format!("{} [y/N]: ", message.trim_end()), designer)?;
+    Ok(is_positive_answer(&answer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positive_answer() {
+        assert!(is_positive_answer("y"));
+        assert!(is_positive_answer(" YES\n"));
+        assert!(!is_positive_answer(""));
+        assert!(!is_positive_answer("no"));
+        assert!(!is_positive_answer("yep"));
+    }
+}
@@ -5,6 +5,7 @@ pub mod system;
 use crate::{
     config::Config,
     errors::{DevrcError, DevrcResult},
+    execute::{get_execution_environment, get_process_environment},
     scope::Scope,
 };
 use std::{
@@ -44,15 +45,58 @@ impl InterpreterKind {
                     .try_borrow_mut()
                     .map_err(|_| DevrcError::RuntimeError)?;
                 let plugin = manager.get_plugin(&interpreter.runtime)?;
-                let options = interpreter
-                    .try_into()
+                let (options, environment) = get_plugin_execution(interpreter, scope, config)?;
+                Ok(plugin.execute(options, code, &environment)?)
+            }
+        }
+    }
+}
+
+impl InterpreterKind {
+    /// Execute code and get its standard output
+    pub fn output(
+        &self,
+        code: &str,
+        scope: &Scope,
+        config: &Config,
+        registry: Rc<RefCell<ExecutionPluginManager>>,
+    ) -> DevrcResult<String> {
+        match self {
+            InterpreterKind::Internal(internal_shell) => internal_shell.output(code, scope, config),
+            InterpreterKind::PluginInterpreter(interpreter) => {
+                let mut manager = (*registry)
+                    .try_borrow_mut()
                     .map_err(|_| DevrcError::RuntimeError)?;
-                Ok(plugin.execute(options, code, &scope.environment)?)
+                manager.get_plugin(&interpreter.runtime)?;
+                let plugin = manager
+                    .get_output_plugin(&interpreter.runtime)
+                    .ok_or(DevrcError::NotImplemented)?;
+                let (options, environment) = get_plugin_execution(interpreter, scope, config)?;
+                Ok(plugin.output(options, code, &environment)?)
             }
         }
     }
 }
 
+/// Get plugin options and environment which code is executed with
+fn get_plugin_execution(
+    interpreter: &PluginInterpreter,
+    scope: &Scope,
+    config: &Config,
+) -> DevrcResult<(ExecutionConfig, indexmap::IndexMap<String, String>)> {
+    let mut options: ExecutionConfig = interpreter
+        .try_into()
+        .map_err(|_| DevrcError::RuntimeError)?;
+    options.args.extend(scope.args.iter().cloned());
+    let environment = get_execution_environment(
+        &scope.environment,
+        &scope.environment_unset,
+        config,
+        &get_process_environment(),
+    );
+    Ok((options, environment))
+}
+
 impl Default for InterpreterKind {
     fn default() -> Self {
         InterpreterKind::Internal(SystemShell::default())
@@ -12,7 +12,7 @@ impl ShebangDetector for String {
             return None;
         }
 
-        let mut parts = first_line[2..].splitn(2, |c| c == ' ' || c == '\t');
+        let mut parts = first_line[2..].splitn(2, [' ', '\t']);
 
         if let Some(value) = parts.next() {
             let mut args = Vec::new();
@@ -39,7 +39,7 @@ impl ShebangDetector for &str {
             return None;
         }
 
-        let mut parts = first_line[2..].splitn(2, |c| c == ' ' || c == '\t');
+        let mut parts = first_line[2..].splitn(2, [' ', '\t']);
 
         if let Some(value) = parts.next() {
             let mut args = Vec::new();
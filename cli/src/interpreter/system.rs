@@ -1,4 +1,9 @@
-use crate::{config::Config, errors::DevrcResult, execute::CommandExt, scope::Scope};
+use crate::{
+    config::Config,
+    errors::DevrcResult,
+    execute::{get_process_environment, CommandExt},
+    scope::Scope,
+};
 use std::{convert::TryFrom, fmt, fmt::Display, marker::PhantomData};
 
 use std::os::unix::{fs::PermissionsExt, process::ExitStatusExt};
@@ -17,7 +22,7 @@ use std::{
 
 use crate::errors::DevrcError;
 
-use tempfile::{Builder, NamedTempFile};
+use tempfile::{Builder, TempPath};
 
 pub const DEFAULT_SHELL: &str = "sh";
 pub const DEFAULT_SHELL_ARG: &str = "-c";
@@ -65,7 +70,12 @@ impl SystemShell {
 
     pub fn execute(&self, code: &str, scope: &Scope, config: &Config) -> DevrcResult<i32> {
         let mut command = Command::new(&self.interpreter);
-        command.export_environment(&scope.environment)?;
+        command.setup_environment(
+            &scope.environment,
+            &scope.environment_unset,
+            config,
+            &get_process_environment(),
+        )?;
 
         if let Some(value) = &config.current_dir {
             command.current_dir(value);
@@ -77,6 +87,12 @@ impl SystemShell {
 
         command.arg(code);
 
+        // Pass-through arguments are available as `$@`, interpreter name is `$0`
+        if !scope.args.is_empty() {
+            command.arg(&self.interpreter);
+            command.args(&scope.args);
+        }
+
         // command.stdin(Stdio::null());
         // command.stdout(Stdio::null());
         // command.stderr(Strio::null());
@@ -101,23 +117,53 @@ impl SystemShell {
         Ok(0)
     }
 
+    /// Execute code and capture standard output, standard error is inherited
+    pub fn output(&self, code: &str, scope: &Scope, config: &Config) -> DevrcResult<String> {
+        let mut command = Command::new(&self.interpreter);
+        command.setup_environment(
+            &scope.environment,
+            &scope.environment_unset,
+            config,
+            &get_process_environment(),
+        )?;
+
+        if let Some(value) = &config.current_dir {
+            command.current_dir(value);
+        }
+
+        command.args(&self.args);
+        command.arg(code);
+        command.stderr(process::Stdio::inherit());
+
+        let output = command.output().map_err(DevrcError::IoError)?;
+
+        match output.status.code() {
+            Some(0) => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+            Some(code) => Err(DevrcError::Code { code }),
+            None => Err(DevrcError::Signal),
+        }
+    }
+
     pub fn execute_script(&self, code: &str, scope: &Scope, config: &Config) -> DevrcResult<i32> {
         let (script_path, _tmp) = create_script_file(code)?;
         set_execute_permission(&script_path)?;
 
         let mut command = Command::new(&script_path);
 
-        command.export_environment(&scope.environment)?;
+        command.setup_environment(
+            &scope.environment,
+            &scope.environment_unset,
+            config,
+            &get_process_environment(),
+        )?;
 
         if let Some(value) = &config.current_dir {
             command.current_dir(value);
         }
 
-        for arg in &self.args {
-            command.arg(arg);
-        }
-
-        command.arg(code);
+        // Script is run by its shebang interpreter,
+        // pass-through arguments are its positional arguments
+        command.args(&scope.args);
 
         // Handle signals
         match command.status() {
@@ -196,10 +242,7 @@ impl<'de> Deserialize<'de> for SystemShell {
                 // TODO: we can simplify this code into few lines
                 let mut elements = Mapping::new();
 
-                while let Some((key, value)) = match access.next_entry::<Value, Value>() {
-                    Ok(value) => value,
-                    Err(error) => return Err(error),
-                } {
+                while let Some((key, value)) = access.next_entry::<Value, Value>()? {
                     elements.insert(key, value);
                 }
 
@@ -218,14 +261,15 @@ impl<'de> Deserialize<'de> for SystemShell {
     }
 }
 
-fn create_script_file(script: &str) -> DevrcResult<(PathBuf, NamedTempFile)> {
-    let tmp = Builder::new().prefix("devrc").tempfile()?;
-
-    let path = tmp.path().to_path_buf();
+/// Write script to temporary file, file is closed to be executable
+/// and removed when returned path is dropped
+fn create_script_file(script: &str) -> DevrcResult<(PathBuf, TempPath)> {
+    let mut tmp = Builder::new().prefix("devrc").tempfile()?;
 
-    let mut f = fs::File::create(&path).unwrap();
+    tmp.write_all(script.as_bytes())?;
 
-    f.write_all(script.as_bytes())?;
+    let tmp = tmp.into_temp_path();
+    let path = tmp.to_path_buf();
 
     Ok((path, tmp))
 }
@@ -17,24 +17,33 @@
 #[macro_use]
 extern crate log;
 
+#[macro_use]
+extern crate lazy_static;
+
 pub mod auth;
 pub mod cache;
+pub mod check;
 pub mod cli;
 pub mod common;
 pub mod config;
 pub mod de;
 pub mod devrcfile;
 pub mod docs;
+pub mod env_expand;
 pub mod env_file;
 pub mod environment;
 pub mod errors;
 pub mod evaluate;
 pub mod execute;
+pub mod fetch;
+pub mod graph;
 pub mod include;
+pub mod input;
 pub mod interpreter;
 pub mod interrupt;
 pub mod loader;
 pub mod netrc;
+pub mod origin;
 pub mod raw;
 pub mod registry;
 pub mod resolver;
@@ -42,8 +51,10 @@ pub mod runner;
 pub mod scope;
 pub mod tasks;
 pub mod template;
+pub mod template_functions;
 pub mod user_agent;
 pub mod utils;
 pub mod variables;
 pub mod variables_parser;
+pub mod vars_file;
 pub mod version;
@@ -1,7 +1,8 @@
-use std::error::Error;
+use std::{error::Error, process};
 
 use devrc::{
     cli::{self, CommandLine},
+    errors::DevrcResult,
     runner::Runner,
 };
 
@@ -26,6 +27,17 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
+    if let Err(error) = execute(&mut runner, opt) {
+        // Secrets must not leak through error messages
+        eprintln!("Error: {}", runner.mask(&format!("{:?}", error)));
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn execute(runner: &mut Runner, opt: CommandLine) -> DevrcResult<()> {
     runner.setup_verbosity(opt.verbose, opt.quiet)?;
 
     if opt.global {
@@ -33,6 +45,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     runner.setup_dry_run(opt.dry_run)?;
+    runner.setup_assume_yes(opt.yes)?;
+    runner.setup_no_input(opt.no_input)?;
 
     if !opt.configs.is_empty() {
         runner.add_files(opt.configs.as_slice().as_ref())?;
@@ -44,6 +58,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         runner.load()?;
     }
 
+    if !opt.vars_files.is_empty() {
+        runner.setup_vars_files(opt.vars_files)?
+    }
+
+    runner.setup_env_variables()?;
+
     if !opt.set.is_empty() {
         runner.setup_variables(opt.set.into())?
     }
@@ -52,10 +72,16 @@ fn main() -> Result<(), Box<dyn Error>> {
         runner.list_tasks_detailed()?;
     } else if opt.list {
         runner.list_tasks()?;
+    } else if let Some(name) = opt.why {
+        runner.why_var(&name, opt.rest)?;
     } else if opt.list_vars {
         runner.list_global_vars()?;
     } else if opt.list_env_vars {
         runner.list_global_env_vars()?;
+    } else if let Some(format) = opt.graph {
+        runner.graph(format, opt.rest, opt.graph_hooks)?;
+    } else if opt.check {
+        runner.check()?;
     } else if opt.describe {
         runner.describe(opt.rest)?;
     } else if opt.dbg {
@@ -0,0 +1,57 @@
+use std::fmt::{self, Display};
+
+use crate::resolver::Location;
+
+/// Where variable or environment variable is defined
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Origin {
+    #[default]
+    None,
+    File {
+        location: Location,
+        task: Option<String>,
+        section: String,
+    },
+    /// Task argument or parameter default
+    Argument { task: String },
+    /// Environment variables with given prefix
+    Environment { prefix: String },
+    /// `--set VAR=VALUE` option
+    CommandLine,
+}
+
+impl Origin {
+    pub fn file(location: &Location, task: Option<&str>, section: &str) -> Self {
+        Origin::File {
+            location: location.clone(),
+            task: task.map(|value| value.to_string()),
+            section: section.to_string(),
+        }
+    }
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::None => write!(f, "unknown"),
+            Origin::File {
+                location,
+                task,
+                section,
+            } => {
+                match location {
+                    Location::StdIn => write!(f, "<stdin>")?,
+                    Location::None => write!(f, "<unknown>")?,
+                    location => write!(f, "{:}", location)?,
+                }
+                if let Some(task) = task {
+                    write!(f, " task `{:}`", task)?;
+                }
+                write!(f, " {:}", section)
+            }
+            Origin::Argument { task } => write!(f, "task `{:}` arguments", task),
+            Origin::Environment { prefix } => write!(f, "environment variable {:}*", prefix),
+            Origin::CommandLine => write!(f, "command line --set"),
+        }
+    }
+}
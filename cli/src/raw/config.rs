@@ -38,6 +38,19 @@ pub struct RawConfig {
 
     #[serde(default, deserialize_with = "deserialize_option_duration")]
     pub cache_ttl: Option<Duration>,
+
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub env_clean: Option<Option<bool>>,
+
+    #[serde(default)]
+    pub env_passthrough: Option<Vec<String>>,
+
+    /// Check templates of all tasks before execution
+    pub strict_templates: Option<bool>,
+
+    /// Prefix of environment variables which override variables, `null` disables overrides
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub variables_env_prefix: Option<Option<String>>,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -1,20 +1,29 @@
 use crate::{
+    config::Config,
     environment::Environment,
     errors::{DevrcError, DevrcResult},
     include::Include,
     loader::LoadingConfig,
+    origin::Origin,
     raw::config::RawConfig,
     resolver::Location,
     tasks::Task,
 };
 
-use std::{fmt::Debug, str::FromStr};
+use std::{cell::RefCell, fmt::Debug, rc::Rc, str::FromStr};
 
+use devrc_plugins::execution::ExecutionPluginManager;
+use indexmap::IndexSet;
 use serde::Deserialize;
 
 use crate::{
-    de::deserialize_some, env_file::EnvFilesInclude, environment::RawEnvironment, tasks::Tasks,
+    de::deserialize_some,
+    env_file::EnvFilesInclude,
+    environment::{EnvValue, RawEnvironment},
+    tasks::Tasks,
+    template::RawTemplates,
     variables::RawVariables,
+    vars_file::{LoadedVariables, VarsFiles},
 };
 
 #[derive(Debug, Deserialize, Clone)]
@@ -43,15 +52,33 @@ pub struct RawDevrcfile {
     version: String,
 
     #[serde(default)]
-    pub environment: RawEnvironment<String>,
+    pub environment: RawEnvironment<EnvValue>,
 
     // Environment variables from files
     #[serde(skip_deserializing)]
     pub files_environment: Environment<String>,
 
+    // Values of environment variables from secret files
+    #[serde(skip_deserializing)]
+    pub files_secrets: IndexSet<String>,
+
     #[serde(default)]
     pub variables: RawVariables,
 
+    #[serde(default)]
+    pub vars_file: VarsFiles,
+
+    // Variables from data files
+    #[serde(skip_deserializing)]
+    pub files_variables: LoadedVariables,
+
+    #[serde(default)]
+    pub templates: RawTemplates,
+
+    // Contents of named templates
+    #[serde(skip_deserializing)]
+    pub loaded_templates: indexmap::IndexMap<String, String>,
+
     #[serde(default)]
     #[serde(rename(deserialize = "include"))]
     pub include: Vec<Include>,
@@ -119,19 +146,93 @@ impl RawDevrcfile {
 
     pub fn load_env_files(&mut self, loading_config: LoadingConfig) -> DevrcResult<()> {
         if let Some(files) = &self.envs_files {
-            let env_vars = files.load(self.location.clone(), loading_config)?;
+            let loaded = files.load(self.location.clone(), loading_config)?;
 
-            for (key, value) in env_vars {
+            for (key, value) in loaded.environment {
                 self.files_environment.insert(key, value);
             }
+            self.files_secrets.extend(loaded.secrets);
         }
         Ok(())
     }
 
+    pub fn load_templates(&mut self, loading_config: &LoadingConfig) -> DevrcResult<()> {
+        self.loaded_templates = self.templates.load(&self.location, loading_config)?;
+        Ok(())
+    }
+
     pub fn prepare(&mut self, loading_config: LoadingConfig) -> DevrcResult<()> {
+        self.setup_loading(&loading_config);
+        self.setup_origin();
+        self.load_templates(&loading_config)?;
+        self.files_variables = self.vars_file.load()?;
         self.load_env_files(loading_config)
     }
 
+    /// Pass file location and loading options to objects which load files or urls
+    pub fn setup_loading(&mut self, loading_config: &LoadingConfig) {
+        let location = self.location.clone();
+        let config = loading_config
+            .clone()
+            .with_cache_ttl(self.config.cache_ttl.or(loading_config.cache_ttl));
+
+        self.variables.setup_loading(&location, &config);
+        self.vars_file.setup_loading(&location, &config);
+
+        for (_, task) in self.get_tasks_mut() {
+            task.setup_loading(&location, &config);
+        }
+    }
+
+    /// Remember where variables and environment variables are defined
+    pub fn setup_origin(&mut self) {
+        let location = self.location.clone();
+
+        self.variables.origin = Origin::file(&location, None, "variables");
+        self.environment.origin = Origin::file(&location, None, "environment");
+        self.vars_file.origin = Origin::file(&location, None, "vars_file");
+
+        for (name, task) in self.get_tasks_mut() {
+            task.setup_origin(&location, name);
+        }
+    }
+
+    /// Set options and plugins which are used by computable variables
+    pub fn setup_execution(
+        &mut self,
+        config: &Config,
+        registry: &Rc<RefCell<ExecutionPluginManager>>,
+    ) {
+        self.variables.setup_execution(config, registry);
+
+        for (_, task) in self.get_tasks_mut() {
+            task.setup_execution(config, registry);
+        }
+    }
+
+    /// Get tasks and hooks with their names
+    fn get_tasks_mut(&mut self) -> impl Iterator<Item = (&str, &mut Task)> {
+        let hooks = vec![
+            ("after_script", &mut self.after_script),
+            ("before_script", &mut self.before_script),
+            ("before_task", &mut self.before_task),
+            ("after_task", &mut self.after_task),
+        ];
+
+        // Task key contains inline parameters after name
+        let tasks = self
+            .tasks
+            .items
+            .iter_mut()
+            .map(|(key, task)| (key.split(' ').next().unwrap_or_default(), task));
+
+        tasks.chain(hooks.into_iter().filter_map(|(name, hook)| {
+            hook.as_mut()
+                .and_then(|task| task.as_mut())
+                .map(|task| (name, task))
+        }))
+    }
+
     pub fn with_kind(self, kind: Kind) -> Self {
         Self { kind, ..self }
     }
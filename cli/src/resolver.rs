@@ -1,5 +1,8 @@
 use serde::Deserialize;
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 use url::Url;
 
 use crate::auth::Auth;
@@ -26,6 +29,32 @@ pub enum PathResolve {
     Pwd,
 }
 
+impl PartialEq for Location {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Location::None, Location::None) | (Location::StdIn, Location::StdIn) => true,
+            (Location::LocalFile(left), Location::LocalFile(right)) => left == right,
+            (Location::Remote { url: left, .. }, Location::Remote { url: right, .. }) => {
+                left == right
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Location {}
+
+impl Location {
+    /// Directory of local file which relative paths are resolved against
+    pub fn get_directory(&self) -> Option<PathBuf> {
+        match self {
+            Location::LocalFile(path) if path.is_file() => path.parent().map(Path::to_path_buf),
+            Location::LocalFile(path) => Some(path.clone()),
+            _ => None,
+        }
+    }
+}
+
 impl Display for Location {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
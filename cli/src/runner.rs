@@ -2,7 +2,6 @@ use std::{cell::RefCell, convert::TryFrom, env, fs, io::Read, path::PathBuf, tim
 
 use devrc_core::{logging::LogLevel, workshop::Designer};
 use indexmap::IndexMap;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use url::Url;
 
 use crate::{
@@ -10,25 +9,29 @@ use crate::{
     cache::Cache,
     devrcfile::Devrcfile,
     docs::DocHelper,
+    env_file::EnvFilesInclude,
     errors::{DevrcError, DevrcResult},
+    fetch::fetch_url,
+    graph::{GraphFormat, TaskGraph},
     include::{FileInclude, Include, UrlInclude},
+    input,
     interrupt::setup_interrupt_handler,
     loader::LoadingConfig,
+    origin::Origin,
     raw::devrcfile::{Kind, RawDevrcfile},
     registry::Registry,
     resolver::{Location, PathResolve},
-    scope::Scope,
+    scope::{Scope, SECRET_MASK},
     tasks::arguments::TaskArguments,
     utils,
     utils::{
         get_absolute_path, get_directory_devrc_file, get_global_devrc_file,
         get_local_user_defined_devrc_file,
     },
-    variables::RawVariables,
+    variables::{RawVariables, ValueKind, VariableValue},
+    vars_file::VarsFiles,
 };
 
-use sha256::digest;
-
 use std::{fmt::Debug, rc::Rc};
 
 use std::io;
@@ -42,6 +45,8 @@ pub struct Runner {
     pub files: Vec<PathBuf>,
     use_global: bool,
     dry_run: bool,
+    assume_yes: bool,
+    no_input: bool,
     rest: Vec<String>,
 
     /// Assembled tasks library
@@ -80,6 +85,8 @@ impl Runner {
             files,
             use_global: false,
             dry_run: false,
+            assume_yes: false,
+            no_input: false,
             rest: vec![],
             devrc: devrcfile,
             global_loaded: false,
@@ -96,6 +103,17 @@ impl Runner {
 
     pub fn setup_dry_run(&mut self, dry_run: bool) -> DevrcResult<()> {
         self.dry_run = dry_run;
+        // Computable variables get options while files are loaded
+        self.devrc.setup_dry_run(dry_run)
+    }
+
+    pub fn setup_assume_yes(&mut self, assume_yes: bool) -> DevrcResult<()> {
+        self.assume_yes = assume_yes || input::is_assume_yes_env();
+        Ok(())
+    }
+
+    pub fn setup_no_input(&mut self, no_input: bool) -> DevrcResult<()> {
+        self.no_input = no_input;
         Ok(())
     }
 
@@ -107,7 +125,53 @@ impl Runner {
         Ok(())
     }
 
-    pub fn setup_variables(&mut self, variables: RawVariables) -> DevrcResult<()> {
+    pub fn setup_variables(&mut self, mut variables: RawVariables) -> DevrcResult<()> {
+        variables.origin = Origin::CommandLine;
+        self.devrc.process_variables(variables)
+    }
+
+    /// Load variables from data files given in command line
+    pub fn setup_vars_files(&mut self, files: Vec<PathBuf>) -> DevrcResult<()> {
+        for file in files {
+            let location = Location::LocalFile(get_absolute_path(&file, None)?);
+            let vars_file = VarsFiles::from(EnvFilesInclude::Simple(file));
+
+            self.devrc.process_loaded_variables(
+                &vars_file.load()?,
+                &Origin::file(&location, None, "--vars-file"),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Override variables by environment variables with configured prefix,
+    /// e.g. `DEVRC_VAR_version=1.2` overrides `version`
+    pub fn setup_env_variables(&mut self) -> DevrcResult<()> {
+        let prefix = match &self.devrc.config.variables_env_prefix {
+            Some(prefix) if !prefix.is_empty() => prefix.clone(),
+            _ => return Ok(()),
+        };
+
+        let mut variables = RawVariables {
+            origin: Origin::Environment {
+                prefix: prefix.clone(),
+            },
+            ..Default::default()
+        };
+        for (key, value) in env::vars_os() {
+            if let (Some(key), Some(value)) = (key.to_str(), value.to_str()) {
+                match key.strip_prefix(&prefix) {
+                    Some(name) if !name.is_empty() => {
+                        variables.add(name, ValueKind::String(value.to_string()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if variables.vars.is_empty() {
+            return Ok(());
+        }
         self.devrc.process_variables(variables)
     }
 
@@ -160,6 +224,8 @@ impl Runner {
         }
 
         self.devrc.setup_dry_run(self.dry_run)?;
+        self.devrc.setup_assume_yes(self.assume_yes)?;
+        self.devrc.setup_no_input(self.no_input)?;
 
         if let Some(level) = &self.log_level {
             self.devrc.setup_log_level(level.clone())?;
@@ -281,6 +347,8 @@ impl Runner {
             Err(error) => return Err(error),
         }
         self.devrc.setup_dry_run(self.dry_run)?;
+        self.devrc.setup_assume_yes(self.assume_yes)?;
+        self.devrc.setup_no_input(self.no_input)?;
 
         if let Some(level) = &self.log_level {
             self.devrc.setup_log_level(level.clone())?;
@@ -301,8 +369,13 @@ impl Runner {
             auth: auth.clone(),
         };
 
+        let mut headers: Vec<(String, String)> = headers.into_iter().collect();
+        headers.extend(auth.get_header());
+
         if let Some(cache_ttl) = self.get_cache_ttl() {
-            if let Some(content) = crate::cache::load(&url, &loading_config, checksum, &cache_ttl) {
+            if let Some(content) =
+                crate::cache::load(&url, &headers, &loading_config, checksum, &cache_ttl)
+            {
                 self.get_logger().debug(
                     &format!("\n==> Loading URL CACHE: `{}` ...", &url),
                     &self.designer.banner(),
@@ -316,88 +389,23 @@ impl Runner {
             &self.designer.banner(),
         );
 
-        let client = reqwest::blocking::Client::new();
-        let mut headers_map: HeaderMap = HeaderMap::new();
-
-        for (key, value) in headers {
-            headers_map.insert(
-                HeaderName::try_from(key.clone()).map_err(|_| {
-                    DevrcError::UrlImportHeadersError {
-                        name: key.clone(),
-                        value: value.clone(),
-                    }
-                })?,
-                HeaderValue::try_from(value.clone()).map_err(|_| {
-                    DevrcError::UrlImportHeadersError {
-                        name: key.clone(),
-                        value: value.clone(),
-                    }
-                })?,
-            );
-        }
-
-        if let Some((key, value)) = auth.get_header() {
-            headers_map.insert(
-                HeaderName::try_from(key.clone()).map_err(|_| {
-                    DevrcError::UrlImportHeadersError {
-                        name: key.clone(),
-                        value: value.clone(),
-                    }
-                })?,
-                HeaderValue::try_from(value.clone()).map_err(|_| {
-                    DevrcError::UrlImportHeadersError {
-                        name: key.clone(),
-                        value: value.clone(),
-                    }
-                })?,
-            );
-        }
-
-        match client.get(url.as_str()).headers(headers_map).send() {
-            Ok(response) if response.status() == 200 => {
-                let content = response.text().map_err(|_| DevrcError::RuntimeError)?;
-                if let Some(control_checksum) = checksum {
-                    let content_checksum = digest(content.as_str());
-
-                    if control_checksum != content_checksum {
-                        return Err(DevrcError::UrlImportChecksumError {
-                            url: url.as_str().to_string(),
-                            control_checksum: control_checksum.to_string(),
-                            content_checksum,
-                        });
-                    }
-                }
-
-                match self.load_from_str(&content, location, Kind::Include, loading_config) {
-                    Ok(_) => {
-                        if self.get_cache_ttl().is_some() {
-                            crate::cache::save(&url, &content)?;
-                        }
-                        Ok(())
-                    }
-                    Err(error) => Err(error),
-                }
-            }
-            Ok(response) => {
+        let content = match fetch_url(&url, &headers, checksum) {
+            Ok(content) => content,
+            Err(DevrcError::UrlImportStatusError { url, status }) => {
                 loading_config.log_level.debug(
-                    &format!(
-                        "Loading FILE error: invalid status code `{:}` ...",
-                        response.status()
-                    ),
+                    &format!("Loading FILE error: invalid status code `{:}` ...", status),
                     &loading_config.designer.banner(),
                 );
-                Err(DevrcError::UrlImportStatusError {
-                    url: url.as_str().to_string(),
-                    status: response.status(),
-                })
-            }
-            Err(error) => {
-                return Err(DevrcError::UrlImportRequestError {
-                    url: url.as_str().to_string(),
-                    inner: error,
-                })
+                return Err(DevrcError::UrlImportStatusError { url, status });
             }
+            Err(error) => return Err(error),
+        };
+
+        self.load_from_str(&content, location, Kind::Include, loading_config)?;
+        if self.get_cache_ttl().is_some() {
+            crate::cache::save(&url, &headers, &content)?;
         }
+        Ok(())
     }
 
     pub fn load_include(
@@ -555,6 +563,13 @@ impl Runner {
         Ok(())
     }
 
+    /// Check templates of all loaded files
+    pub fn check(&self) -> DevrcResult<()> {
+        self.devrc.check_templates()?;
+        println!("All templates are valid");
+        Ok(())
+    }
+
     /// Execute given commands
     pub fn run(&mut self, params: Vec<String>) -> DevrcResult<()> {
         self.rest = params;
@@ -591,6 +606,17 @@ impl Runner {
                 width = offset,
                 max_taskname_width = max_taskname_width
             );
+
+            for line in task.format_parameters_details(&self.designer)? {
+                println!(
+                    "{:width$}{:max_taskname_width$}   {}",
+                    "",
+                    "",
+                    line,
+                    width = offset,
+                    max_taskname_width = max_taskname_width
+                );
+            }
         }
 
         Ok(())
@@ -662,12 +688,14 @@ impl Runner {
 
         for (name, value) in &scope.variables {
             println!(
-                "{:width$}{}{:max_variable_name_width$}{} = \"{}\"",
+                "{:width$}{}{:max_variable_name_width$}{} = {}  # {}",
                 "",
                 self.designer.variable().prefix(),
                 name.get_name(),
                 self.designer.variable().suffix(),
-                value.get_rendered_value(),
+                self.mask_variable(scope, value)?
+                    .replace('\n', &format!("\n{:width$}", "", width = 2)),
+                value.origin,
                 width = 2,
                 max_variable_name_width = max_variable_name_width
             );
@@ -675,17 +703,92 @@ impl Runner {
         Ok(())
     }
 
+    /// Show where variable is defined and which values it overrides
+    ///
+    /// Variable is searched in global scope or in scope of given task.
+    pub fn why_var(&self, name: &str, params: Vec<String>) -> DevrcResult<()> {
+        let scope = match params.first() {
+            Some(task_name) => self.devrc.find_task(task_name)?.get_scope(
+                task_name,
+                Rc::clone(&self.devrc.scope),
+                &TaskArguments::default(),
+            )?,
+            None => self.devrc.get_scope_copy()?,
+        };
+
+        let value = scope
+            .compute_execution_scope()?
+            .variables
+            .into_iter()
+            .find(|(key, _)| key.name == name)
+            .map(|(_, value)| value)
+            .ok_or(DevrcError::VariableNotFound(name.to_string()))?;
+
+        println!(
+            "Variable {}{}{}:",
+            self.designer.variable().prefix(),
+            name,
+            self.designer.variable().suffix()
+        );
+
+        for (idx, layer) in value.get_overrides_chain().into_iter().enumerate() {
+            let action = if idx == 0 { "defined" } else { "overridden" };
+            println!("  {} in {}", action, layer.origin);
+            println!("    template: {:?}", scope.mask(&layer.original));
+            println!(
+                "    value:    {}",
+                self.mask_variable(&scope, layer)?
+                    .replace('\n', &format!("\n{:width$}", "", width = 14))
+            );
+        }
+        Ok(())
+    }
+
+    /// Display value of variable, secret variable is masked as a whole
+    fn mask_variable(&self, scope: &Scope, value: &VariableValue) -> DevrcResult<String> {
+        if scope.is_secret(&value.get_rendered_value()) {
+            return Ok(SECRET_MASK.to_string());
+        }
+        Ok(scope.mask(&value.get_display_value()?))
+    }
+
     pub fn list_env_vars(&self, scope: &Scope) -> DevrcResult<()> {
-        let max_variable_name_width = scope.environment.get_max_key_width();
+        let max_variable_name_width = scope
+            .environment_unset
+            .iter()
+            .map(|name| name.len())
+            .fold(scope.environment.get_max_key_width(), usize::max);
 
         for (name, value) in &scope.environment {
             println!(
-                "{:width$}{}{:max_variable_name_width$}{} = \"{}\"",
+                "{:width$}{}{:max_variable_name_width$}{} = \"{}\"  # {}",
+                "",
+                self.designer.evariable().prefix(),
+                name,
+                self.designer.evariable().suffix(),
+                scope.mask_value(value),
+                scope
+                    .environment_origins
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_default(),
+                width = 2,
+                max_variable_name_width = max_variable_name_width
+            );
+        }
+
+        for name in &scope.environment_unset {
+            println!(
+                "{:width$}{}{:max_variable_name_width$}{}  # unset, {}",
                 "",
                 self.designer.evariable().prefix(),
                 name,
                 self.designer.evariable().suffix(),
-                value,
+                scope
+                    .environment_origins
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_default(),
                 width = 2,
                 max_variable_name_width = max_variable_name_width
             );
@@ -709,6 +812,14 @@ impl Runner {
                 // width = 2,
             );
 
+            let parameters = task.format_parameters_details(&self.designer)?;
+            if !parameters.is_empty() {
+                println!("Parameters:");
+                for line in parameters {
+                    println!("  {}", line);
+                }
+            }
+
             if let Some(example) = task.get_example() {
                 println!("Examples: \n{}", example);
             }
@@ -732,6 +843,26 @@ impl Runner {
         Ok(())
     }
 
+    /// Print dependencies and subtasks graph for given tasks or for all tasks
+    pub fn graph(
+        &self,
+        format: GraphFormat,
+        params: Vec<String>,
+        with_hooks: bool,
+    ) -> DevrcResult<()> {
+        let graph = TaskGraph::build(&self.devrc, &self.registry, &params, with_hooks)?;
+        println!("{}", graph.render(format));
+        Ok(())
+    }
+
+    /// Replace all known secret values in given text
+    pub fn mask(&self, text: &str) -> String {
+        match (*self.devrc.scope).try_borrow() {
+            Ok(scope) => scope.mask(text),
+            Err(_) => text.to_owned(),
+        }
+    }
+
     pub fn get_calculated_scope(&self, _scope: &Scope) {}
 
     pub fn diagnostic(&mut self, params: Vec<String>) {
@@ -1,43 +1,227 @@
-use std::{cell::RefCell, convert::TryFrom};
+use std::{cell::RefCell, collections::HashMap, convert::TryFrom, env, path::PathBuf};
 
+use indexmap::IndexSet;
 use tera::Context;
 
 use crate::{
-    environment::{Environment, RawEnvironment},
+    check::get_used_names,
+    env_expand::{expand_references, has_references},
+    env_file::LoadedEnvironment,
+    environment::{EnvValue, Environment, RawEnvironment},
     errors::{DevrcError, DevrcResult},
-    evaluate::Evaluatable,
+    origin::Origin,
     variables::{self, RawVariables, VariableKey, VariableValue, Variables},
+    vars_file::LoadedVariables,
 };
 use std::rc::Rc;
 
-#[derive(Debug, Default, PartialEq, Eq)]
+pub const SECRET_MASK: &str = "***";
+
+/// Shorter secrets such as `1` or `dev` are masked only as whole values,
+/// otherwise they corrupt unrelated output
+const MIN_MASKED_SUBSTRING_LENGTH: usize = 5;
+
+#[derive(Debug, Default)]
 pub struct Scope {
     pub name: String,
     pub variables: Variables,
     pub environment: indexmap::IndexMap<String, String>,
+    /// Where environment variables are defined
+    pub environment_origins: indexmap::IndexMap<String, Origin>,
+    /// Inherited environment variables which are removed
+    pub environment_unset: IndexSet<String>,
+    /// Values of environment variables before definitions of raw environments
+    /// are applied, in order of definitions
+    pub environment_bases: indexmap::IndexMap<String, Vec<(Origin, Option<String>)>>,
+    /// Values that must be masked in output
+    pub secrets: IndexSet<String>,
+    /// Pass-through arguments given after `--`
+    pub args: Vec<String>,
+    /// Directory of Devrcfile where templates of scope are defined
+    pub directory: Option<PathBuf>,
     pub parent: Option<Rc<RefCell<Scope>>>,
     pub root: Option<Rc<RefCell<Scope>>>,
+    /// Computable variables visible from scope by name,
+    /// ancestors aren't changed while scope is rendered
+    pub(crate) computed_vars: RefCell<Option<Rc<HashMap<String, VariableValue>>>>,
+}
+
+// Origins are not compared as they don't affect execution
+impl PartialEq for Scope {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.variables == other.variables
+            && self.environment == other.environment
+            && self.environment_unset == other.environment_unset
+            && self.secrets == other.secrets
+            && self.args == other.args
+            && self.parent == other.parent
+            && self.root == other.root
+    }
 }
 
+impl Eq for Scope {}
+
 impl Scope {
     /// Add variable to scope
     pub fn insert_var(&mut self, key: VariableKey, value: VariableValue) -> Option<VariableValue> {
+        self.computed_vars.replace(None);
         self.variables.insert(key, value)
     }
 
     pub fn process_binding(&mut self, key: &str, value: &str) -> DevrcResult<()> {
-        self.variables.insert(
-            VariableKey::try_from(key.to_string())?,
-            VariableValue::new(key, value).with_render_value(self)?,
-        );
+        let key = VariableKey::try_from(key.to_string())?;
+        let value = VariableValue::new(&key.name, value)
+            .with_render_value(self)?
+            .with_origin(self.get_argument_origin(), self.find_var(&key));
+
+        self.insert_var(key, value);
         Ok(())
     }
 
+    pub fn process_list_binding(&mut self, key: &str, values: &[String]) -> DevrcResult<()> {
+        let key = VariableKey::try_from(key.to_string())?;
+        let value = VariableValue::from_list(&key.name, values)
+            .with_origin(self.get_argument_origin(), self.find_var(&key));
+
+        self.insert_var(key, value);
+        Ok(())
+    }
+
+    fn get_argument_origin(&self) -> Origin {
+        Origin::Argument {
+            task: self.name.clone(),
+        }
+    }
+
+    /// Find variable in current scope or its ancestors
+    pub fn find_var(&self, key: &VariableKey) -> Option<VariableValue> {
+        if let Some(value) = self.variables.get(key) {
+            return Some(value.clone());
+        }
+
+        let mut parent_link = self.parent.clone();
+
+        while let Some(parent) = parent_link {
+            let parent_scope = parent.try_borrow().ok()?;
+            if let Some(value) = parent_scope.variables.get(key) {
+                return Some(value.clone());
+            }
+            parent_link = parent_scope.parent.clone();
+        }
+        None
+    }
+
     /// Add environment variable to scope
     pub fn insert_env(&mut self, key: &str, value: &str) -> Option<String> {
+        self.environment_unset.shift_remove(key);
         self.environment.insert(key.to_owned(), value.to_owned())
     }
 
+    /// Remove environment variable from scope and inherited environment
+    pub fn unset_env(&mut self, key: &str) -> Option<String> {
+        self.environment_unset.insert(key.to_owned());
+        self.environment.shift_remove(key)
+    }
+
+    /// Get value of environment variable which is passed to child process
+    /// if it isn't defined in current scope
+    fn get_current_env(&self, key: &str) -> DevrcResult<Option<String>> {
+        if let Some(value) = self.environment.get(key) {
+            return Ok(Some(value.clone()));
+        }
+        if self.environment_unset.contains(key) {
+            return Ok(None);
+        }
+
+        let mut parent_link = self.parent.clone();
+
+        while let Some(parent) = parent_link {
+            let parent_scope = parent.try_borrow().map_err(|_| DevrcError::RuntimeError)?;
+            if let Some(value) = parent_scope.environment.get(key) {
+                return Ok(Some(value.clone()));
+            }
+            if parent_scope.environment_unset.contains(key) {
+                return Ok(None);
+            }
+            parent_link = parent_scope.parent.clone();
+        }
+
+        Ok(env::var(key).ok())
+    }
+
+    /// Mark value as secret in current and root scopes
+    pub fn add_secret(&mut self, value: &str) -> DevrcResult<()> {
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        self.secrets.insert(value.to_owned());
+
+        if let Some(root) = self.root.as_ref() {
+            let mut root_scope = (**root)
+                .try_borrow_mut()
+                .map_err(|_| DevrcError::RuntimeError)?;
+            root_scope.secrets.insert(value.to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn process_secrets(&mut self, secrets: &IndexSet<String>) -> DevrcResult<()> {
+        for value in secrets {
+            self.add_secret(value)?;
+        }
+        Ok(())
+    }
+
+    /// Get secrets of current scope and all its ancestors
+    pub fn get_secrets(&self) -> IndexSet<String> {
+        let mut secrets = self.secrets.clone();
+        let mut parent_link = self.parent.clone();
+
+        while let Some(parent) = parent_link {
+            parent_link = match parent.try_borrow() {
+                Ok(parent_scope) => {
+                    secrets.extend(parent_scope.secrets.iter().cloned());
+                    parent_scope.parent.clone()
+                }
+                Err(_) => None,
+            };
+        }
+        secrets
+    }
+
+    /// Check if value is secret as a whole
+    pub fn is_secret(&self, value: &str) -> bool {
+        self.get_secrets().contains(value)
+    }
+
+    /// Mask value of variable, short secrets are masked only as whole values
+    pub fn mask_value(&self, value: &str) -> String {
+        if self.is_secret(value) {
+            return SECRET_MASK.to_string();
+        }
+        self.mask(value)
+    }
+
+    /// Replace secret values in given text by `***`
+    pub fn mask(&self, text: &str) -> String {
+        let mut secrets: Vec<String> = self
+            .get_secrets()
+            .into_iter()
+            .filter(|value| value.chars().count() >= MIN_MASKED_SUBSTRING_LENGTH)
+            .collect();
+
+        // Longest values first to not leave parts of overlapping secrets
+        secrets.sort_by_key(|value| std::cmp::Reverse(value.len()));
+
+        let mut result = text.to_owned();
+        for secret in secrets {
+            result = result.replace(&secret, SECRET_MASK);
+        }
+        result
+    }
+
     pub fn get_var(&self, key: &VariableKey) -> Option<&VariableValue> {
         self.variables.get(key)
     }
@@ -47,57 +231,359 @@ impl Scope {
     }
 
     pub fn process_raw_vars(&mut self, variables: &RawVariables) -> DevrcResult<()> {
+        check_circular_variables(variables)?;
+
+        let mut names = IndexSet::new();
         for (original_key, original_value) in &variables.vars {
-            match original_value {
+            let value: VariableValue = match original_value {
                 variables::ValueKind::None => return Err(DevrcError::EmptyVariable),
                 variables::ValueKind::String(inner) => {
-                    let key = VariableKey::try_from(original_key.clone())?;
-
-                    let value: VariableValue = if key.raw {
+                    if VariableKey::try_from(original_key.clone())?.raw {
                         VariableValue::new(original_key, inner).as_raw()?
                     } else {
                         VariableValue::new(original_key, inner).with_render_value(self)?
-                    };
+                    }
+                }
+                variables::ValueKind::File(file) => file.evaluate(original_key, self)?,
+                variables::ValueKind::Http(http) => http.evaluate(original_key, self)?,
+                variables::ValueKind::Computable(computable) => {
+                    computable.evaluate(original_key, self)?
+                }
+                variables::ValueKind::List(_) | variables::ValueKind::Map(_) => {
+                    VariableValue::from_structured(
+                        original_key,
+                        original_value
+                            .get_structured(original_key)?
+                            .unwrap_or_default(),
+                        VariableKey::try_from(original_key.clone())?.raw,
+                        self,
+                    )?
+                }
+            };
 
-                    self.variables.insert(key.clone(), value.clone());
+            let key = VariableKey::try_from(original_key.clone())?;
+            names.insert(key.name.clone());
+            self.set_var(key, value, &variables.origin)?;
+        }
+        self.rerender_dependents(&names)
+    }
 
-                    if key.set_global && self.root.is_some() {
-                        let mut root_scope = (**(self.root.as_ref().unwrap()))
-                            .try_borrow_mut()
-                            .map_err(|_| DevrcError::RuntimeError)?;
-                        root_scope.insert_var(key, value);
-                    }
+    /// Add variables loaded from data files, values aren't rendered
+    pub fn process_loaded_vars(
+        &mut self,
+        variables: &LoadedVariables,
+        origin: &Origin,
+    ) -> DevrcResult<()> {
+        let mut names = IndexSet::new();
+        for (name, value) in &variables.variables {
+            let content = match value {
+                tera::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            let key = VariableKey::try_from(name.clone())?;
+            let value = VariableValue::from_content(&key.name, &content, Some(value.clone()));
+            names.insert(key.name.clone());
+            self.set_var(key, value, origin)?;
+        }
+        self.rerender_dependents(&names)?;
+        self.process_secrets(&variables.secrets)
+    }
+
+    /// Insert processed variable, variables which depend on it
+    /// are rendered again by `rerender_dependents` after all of them are inserted
+    fn set_var(
+        &mut self,
+        key: VariableKey,
+        value: VariableValue,
+        origin: &Origin,
+    ) -> DevrcResult<()> {
+        let value = value.with_origin(origin.clone(), self.find_var(&key));
+
+        self.insert_var(key.clone(), value.clone());
+
+        if key.secret {
+            self.add_secret(&value.get_value()?)?;
+        }
+
+        if let (true, Some(root)) = (key.set_global, self.root.as_ref()) {
+            let mut root_scope = (**root)
+                .try_borrow_mut()
+                .map_err(|_| DevrcError::RuntimeError)?;
+            root_scope.insert_var(key, value);
+        }
+        Ok(())
+    }
+
+    /// Override variables and return names of changed variables,
+    /// including ones which are rendered again
+    pub fn process_override_vars(
+        &mut self,
+        variables: &RawVariables,
+    ) -> DevrcResult<IndexSet<String>> {
+        let mut changed = IndexSet::new();
+        for key in variables.vars.keys() {
+            changed.insert(VariableKey::try_from(key.clone())?.name);
+        }
+
+        self.process_raw_vars(variables)?;
+
+        let dependents = self.get_dependents(&changed)?;
+        changed.extend(dependents.into_iter().map(|key| key.name));
+        Ok(changed)
+    }
+
+    /// Get variables which depend on given names directly or through
+    /// other variables, sorted in rendering order
+    pub fn get_dependents(&self, names: &IndexSet<String>) -> DevrcResult<Vec<VariableKey>> {
+        let mut affected: IndexSet<String> = names.clone();
+        loop {
+            let count = affected.len();
+            for (key, value) in &self.variables {
+                if !affected.contains(&key.name)
+                    && value
+                        .dependencies
+                        .iter()
+                        .any(|name| affected.contains(name))
+                {
+                    affected.insert(key.name.clone());
+                }
+            }
+            if affected.len() == count {
+                break;
+            }
+        }
+
+        let mut sorter = DependentsSorter {
+            dependencies: self
+                .variables
+                .iter()
+                .map(|(key, value)| (key.name.as_str(), value.dependencies.as_slice()))
+                .collect(),
+            affected: &affected,
+            stack: Vec::new(),
+            order: IndexSet::new(),
+        };
+        for name in &affected {
+            sorter.visit(name)?;
+        }
+
+        let mut result = Vec::new();
+        for name in sorter.order.iter().filter(|name| !names.contains(*name)) {
+            result.extend(
+                self.variables
+                    .keys()
+                    .filter(|key| &key.name == name)
+                    .cloned(),
+            );
+        }
+        Ok(result)
+    }
+
+    /// Render again variables which depend on given inserted variables,
+    /// inserted variables which refer to ones inserted after them are rendered again too
+    fn rerender_dependents(&mut self, names: &IndexSet<String>) -> DevrcResult<()> {
+        let mut changed = names.clone();
+        for (key, value) in &self.variables {
+            if let Some(index) = names.get_index_of(&key.name) {
+                if value.dependencies.iter().any(|name| {
+                    names
+                        .get_index_of(name)
+                        .is_some_and(|position| position > index)
+                }) {
+                    changed.shift_remove(&key.name);
                 }
-                _ => return Err(DevrcError::VariableTypeNotImplemented),
             }
         }
+
+        for key in self.get_dependents(&changed)? {
+            let mut value = match self.variables.get(&key) {
+                Some(value) => value.clone(),
+                None => continue,
+            };
+            value.rerender(self)?;
+            if key.secret && value.computed.is_none() {
+                self.add_secret(&value.get_rendered_value())?;
+            }
+            self.insert_var(key, value);
+        }
         Ok(())
     }
 
-    pub fn process_raw_env_vars(&mut self, variables: &RawEnvironment<String>) -> DevrcResult<()> {
+    /// Render again environment variables which refer to changed variables
+    /// and are still defined by given environment
+    pub fn rerender_env_vars(
+        &mut self,
+        variables: &RawEnvironment<EnvValue>,
+        changed: &IndexSet<String>,
+    ) -> DevrcResult<()> {
         for (key, value) in &variables.vars {
-            let result = value.evaluate(key, self);
-            match result {
-                Ok(rendered_value) => {
-                    self.environment
-                        .insert(key.to_owned(), rendered_value.to_owned());
+            let position = match self.environment_bases.get(key).and_then(|bases| {
+                bases
+                    .iter()
+                    .position(|(origin, _)| origin == &variables.origin)
+            }) {
+                Some(position) => position,
+                None => continue,
+            };
+
+            // Paths are added to base value which could be changed by previous definitions
+            if !matches!(value, EnvValue::Modify(_)) {
+                let mut used = Vec::new();
+                for template in value.get_templates() {
+                    used.extend(get_used_names(key, template)?);
+                }
+                if !used.iter().any(|name| changed.contains(name)) {
+                    continue;
+                }
+            }
+
+            let base = self.environment_bases[key][position].1.clone();
+            let rendered_value = value.evaluate(key, self, base)?;
+
+            // Value of last definition is used, others are bases of next definitions
+            let next = self
+                .environment_bases
+                .get_mut(key)
+                .and_then(|bases| bases.get_mut(position + 1));
+            match (next, rendered_value) {
+                (Some((_, next_base)), rendered_value) => *next_base = rendered_value,
+                (None, Some(rendered_value)) => {
+                    self.insert_env(key, &rendered_value);
+                }
+                (None, None) => {
+                    self.unset_env(key);
                 }
-                Err(error) => return Err(error),
             }
         }
         Ok(())
     }
 
+    pub fn process_raw_env_vars(
+        &mut self,
+        variables: &RawEnvironment<EnvValue>,
+    ) -> DevrcResult<()> {
+        for (key, value) in &variables.vars {
+            let current = self.get_current_env(key)?;
+            self.environment_bases
+                .entry(key.to_owned())
+                .or_default()
+                .push((variables.origin.clone(), current.clone()));
+            match value.evaluate(key, self, current)? {
+                Some(rendered_value) => self.insert_env(key, &rendered_value),
+                None => self.unset_env(key),
+            };
+            self.environment_origins
+                .insert(key.to_owned(), variables.origin.clone());
+        }
+        Ok(())
+    }
+
     pub fn process_rendered_env_vars(
         &mut self,
         variables: &Environment<String>,
+        origin: &Origin,
     ) -> DevrcResult<()> {
         for (key, value) in variables.into_iter() {
-            self.environment.insert(key.to_owned(), value.to_owned());
+            self.insert_env(key, value);
+            self.environment_bases.shift_remove(key);
+            self.environment_origins
+                .insert(key.to_owned(), origin.clone());
         }
         Ok(())
     }
 
+    /// Add environment variables and secrets loaded from env files,
+    /// references left in values are resolved in current scope
+    pub fn process_loaded_env(
+        &mut self,
+        loaded: &LoadedEnvironment,
+        origin: &Origin,
+    ) -> DevrcResult<()> {
+        for (key, value) in &loaded.environment {
+            if !has_references(value) {
+                self.insert_env(key, value);
+            } else {
+                let expanded = expand_references(value, |name| self.get_reference_value(name));
+                if loaded.secrets.contains(value) {
+                    self.add_secret(&expanded)?;
+                }
+                self.insert_env(key, &expanded);
+            }
+            self.environment_bases.shift_remove(key);
+            self.environment_origins
+                .insert(key.to_owned(), origin.clone());
+        }
+
+        for value in &loaded.secrets {
+            if !has_references(value) {
+                self.add_secret(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get value of name referenced from env file: environment variable
+    /// or variable of current scope
+    fn get_reference_value(&self, name: &str) -> Option<String> {
+        if let Ok(Some(value)) = self.get_current_env(name) {
+            return Some(value);
+        }
+        VariableKey::try_from(name.to_string())
+            .ok()
+            .and_then(|key| self.find_var(&key))
+            .map(|value| value.get_rendered_value())
+    }
+
+    /// Compute lazy variables which are referenced by template and add them to context
+    pub fn insert_computed_vars(&self, template: &str, context: &mut Context) -> DevrcResult<()> {
+        let computed_vars = self.get_computed_vars()?;
+        if computed_vars.values().all(|value| !value.is_lazy()) {
+            return Ok(());
+        }
+
+        for name in get_used_names("computed", template)? {
+            match computed_vars.get(&name) {
+                Some(value) if value.is_lazy() => {
+                    value.get_value()?;
+                    context.insert(name, &value.get_context_value());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Get computable variables visible from scope, they are collected once
+    fn get_computed_vars(&self) -> DevrcResult<Rc<HashMap<String, VariableValue>>> {
+        if let Some(computed_vars) = self.computed_vars.borrow().as_ref() {
+            return Ok(Rc::clone(computed_vars));
+        }
+
+        // Only computable variables are copied, outer ones are overridden by inner
+        let get_computed = |variables: &Variables| -> Vec<(String, VariableValue)> {
+            variables
+                .iter()
+                .filter(|(_, value)| value.computed.is_some())
+                .map(|(key, value)| (key.get_name(), value.clone()))
+                .collect()
+        };
+        let mut scopes = vec![get_computed(&self.variables)];
+        let mut parent_link = self.parent.clone();
+        while let Some(parent) = parent_link {
+            let parent_scope = parent.try_borrow().map_err(|_| DevrcError::RuntimeError)?;
+            scopes.push(get_computed(&parent_scope.variables));
+            parent_link = parent_scope.parent.clone();
+        }
+
+        let mut computed_vars = HashMap::new();
+        for variables in scopes.into_iter().rev() {
+            computed_vars.extend(variables);
+        }
+        let computed_vars = Rc::new(computed_vars);
+        self.computed_vars.replace(Some(Rc::clone(&computed_vars)));
+        Ok(computed_vars)
+    }
+
     pub fn compute_execution_scope(&self) -> DevrcResult<Scope> {
         let mut scope = Scope {
             name: format!("execution_scope: {:}", &self.name),
@@ -138,21 +624,55 @@ impl Scope {
                 scope.insert_var(key.clone(), value.clone());
             }
 
+            for key in &ancestor_scope.environment_unset {
+                scope.unset_env(key);
+            }
             for (key, value) in &ancestor_scope.environment {
                 scope.insert_env(key, value);
             }
+            scope
+                .environment_origins
+                .extend(ancestor_scope.environment_origins.clone());
+
+            scope.secrets.extend(ancestor_scope.secrets.iter().cloned());
+
+            if ancestor_scope.directory.is_some() {
+                scope.directory = ancestor_scope.directory.clone();
+            }
         }
 
         for (key, value) in &self.variables {
             scope.insert_var(key.clone(), value.clone());
         }
 
+        for key in &self.environment_unset {
+            scope.unset_env(key);
+        }
         for (key, value) in &self.environment {
             scope.insert_env(key, value);
         }
+        scope
+            .environment_origins
+            .extend(self.environment_origins.clone());
+
+        scope.secrets.extend(self.secrets.iter().cloned());
+        scope.args = self.args.clone();
+
+        if self.directory.is_some() {
+            scope.directory = self.directory.clone();
+        }
 
         Ok(scope)
     }
+
+    /// Get directory of Devrcfile where templates of scope or its ancestors are defined
+    pub fn get_directory(&self) -> Option<PathBuf> {
+        match (&self.directory, &self.parent) {
+            (Some(directory), _) => Some(directory.clone()),
+            (None, Some(parent)) => parent.try_borrow().ok()?.get_directory(),
+            (None, None) => None,
+        }
+    }
 }
 
 pub fn child_scope(scope_ref: Rc<RefCell<Scope>>, name: &str) -> Scope {
@@ -191,6 +711,12 @@ impl Clone for Scope {
         for (name, value) in &self.environment {
             scope.insert_env(name, value);
         }
+        scope.environment_unset = self.environment_unset.clone();
+        scope.environment_origins = self.environment_origins.clone();
+
+        scope.secrets = self.secrets.clone();
+        scope.args = self.args.clone();
+        scope.directory = self.directory.clone();
 
         scope
     }
@@ -202,6 +728,8 @@ impl TryFrom<&Scope> for Context {
     fn try_from(source: &Scope) -> Result<Self, Self::Error> {
         let mut context: Context = Self::new();
 
+        context.insert("devrc", &HashMap::from([("args", &source.args)]));
+
         let mut ancestors = Vec::new();
 
         let mut parent_link = source.parent.clone();
@@ -231,21 +759,85 @@ impl TryFrom<&Scope> for Context {
                 .try_borrow()
                 .map_err(|_| DevrcError::RuntimeError)?;
 
-            for (key, value) in &scope.variables {
-                context.insert(key.get_name(), &value.get_rendered_value());
+            for (key, value) in scope.variables.iter().filter(|(_, value)| !value.is_lazy()) {
+                context.insert(key.get_name(), &value.get_context_value());
             }
         }
 
-        for (key, value) in &source.variables {
-            context.insert(key.get_name(), &value.get_rendered_value());
+        for (key, value) in source
+            .variables
+            .iter()
+            .filter(|(_, value)| !value.is_lazy())
+        {
+            context.insert(key.get_name(), &value.get_context_value());
         }
         Ok(context)
     }
 }
 
+/// Sorts variables so that dependencies are rendered first
+/// Check that variables defined together don't refer to each other in a cycle,
+/// otherwise rendering fails on the first undefined one
+fn check_circular_variables(variables: &RawVariables) -> DevrcResult<()> {
+    let dependencies = variables.get_dependencies()?;
+    let names: IndexSet<String> = dependencies.keys().cloned().collect();
+
+    let mut sorter = DependentsSorter {
+        dependencies: dependencies
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_slice()))
+            .collect(),
+        affected: &names,
+        stack: Vec::new(),
+        order: IndexSet::new(),
+    };
+    for name in &names {
+        sorter.visit(name)?;
+    }
+    Ok(())
+}
+
+struct DependentsSorter<'a> {
+    dependencies: HashMap<&'a str, &'a [String]>,
+    affected: &'a IndexSet<String>,
+    stack: Vec<String>,
+    order: IndexSet<String>,
+}
+
+impl DependentsSorter<'_> {
+    fn visit(&mut self, name: &str) -> DevrcResult<()> {
+        if self.order.contains(name) {
+            return Ok(());
+        }
+        if let Some(position) = self.stack.iter().position(|value| value == name) {
+            let mut names = self.stack[position..].to_vec();
+            names.push(name.to_string());
+            return Err(DevrcError::CircularVariables(names));
+        }
+
+        self.stack.push(name.to_string());
+        let dependencies = self.dependencies.get(name).copied().unwrap_or_default();
+        for dependency in dependencies {
+            if self.affected.contains(dependency) {
+                self.visit(dependency)?;
+            }
+        }
+        self.stack.pop();
+
+        self.order.insert(name.to_string());
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::path::PathBuf;
+
     use super::*;
+    use crate::{
+        environment::{PathsModification, PATH_SEPARATOR},
+        resolver::Location,
+    };
 
     #[test]
     fn test_scope() {
@@ -273,4 +865,282 @@ mod tests {
             Some(&"env_var_2_val".to_owned())
         );
     }
+
+    #[test]
+    fn test_mask_secrets() {
+        let scope = Rc::new(RefCell::new(Scope::default()));
+
+        let mut variables = RawVariables::default();
+        variables.add(
+            "token +secret",
+            variables::ValueKind::String("s3cr3t".to_owned()),
+        );
+        variables.add(
+            "url",
+            variables::ValueKind::String("https://{{ token }}@host".to_owned()),
+        );
+        scope.borrow_mut().process_raw_vars(&variables).unwrap();
+
+        let mut child = child_scope(Rc::clone(&scope), "child");
+        child.add_secret("local").unwrap();
+
+        assert_eq!(
+            child.mask("curl https://s3cr3t@host local"),
+            "curl https://***@host ***"
+        );
+        assert_eq!(scope.borrow().mask("local s3cr3t"), "*** ***");
+
+        child.add_secret("dev").unwrap();
+        assert_eq!(child.mask("deploy to dev"), "deploy to dev");
+        assert_eq!(child.mask_value("dev"), "***");
+    }
+
+    #[test]
+    fn test_passthrough_args() {
+        let scope = Rc::new(RefCell::new(Scope::default()));
+
+        let mut child = child_scope(Rc::clone(&scope), "child");
+        child.args = vec!["--nocapture".to_owned(), "my filter".to_owned()];
+
+        let execution_scope = child.compute_execution_scope().unwrap();
+
+        assert_eq!(
+            crate::template::render_string(
+                "test",
+                "cargo test {{ devrc.args | shell_quote }} {{ devrc.args | first }}",
+                &execution_scope
+            )
+            .unwrap(),
+            "cargo test --nocapture 'my filter' --nocapture"
+        );
+    }
+
+    #[test]
+    fn test_variables_origin() {
+        let location = Location::LocalFile(PathBuf::from("Devrcfile"));
+        let scope = Rc::new(RefCell::new(Scope::default()));
+
+        let mut variables = RawVariables::default();
+        variables.add("version", variables::ValueKind::String("1.0".to_owned()));
+        variables.origin = Origin::file(&location, None, "variables");
+        scope.borrow_mut().process_raw_vars(&variables).unwrap();
+
+        let mut child = child_scope(Rc::clone(&scope), "deploy");
+        let mut variables = RawVariables::default();
+        variables.add(
+            "version",
+            variables::ValueKind::String("{{ version }}-rc".to_owned()),
+        );
+        variables.origin = Origin::file(&location, Some("deploy"), "variables");
+        child.process_raw_vars(&variables).unwrap();
+
+        let key = VariableKey::try_from("version".to_string()).unwrap();
+        let value = child.find_var(&key).unwrap();
+        let chain = value.get_overrides_chain();
+
+        assert_eq!(
+            chain
+                .iter()
+                .map(|value| (
+                    value.origin.to_string(),
+                    value.original.clone(),
+                    value.get_rendered_value()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "Devrcfile variables".to_owned(),
+                    "1.0".to_owned(),
+                    "1.0".to_owned()
+                ),
+                (
+                    "Devrcfile task `deploy` variables".to_owned(),
+                    "{{ version }}-rc".to_owned(),
+                    "1.0-rc".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_override_vars() {
+        let mut scope = Scope::default();
+
+        let mut variables = RawVariables::default();
+        variables.add("version", variables::ValueKind::String("1.0".to_owned()));
+        variables.add(
+            "tag",
+            variables::ValueKind::String("v{{ version }}".to_owned()),
+        );
+        variables.add(
+            "image",
+            variables::ValueKind::String("app:{{ tag }}".to_owned()),
+        );
+        variables.add(
+            "template +raw",
+            variables::ValueKind::String("{{ version }}".to_owned()),
+        );
+        scope.process_raw_vars(&variables).unwrap();
+
+        let mut environment = RawEnvironment::default();
+        environment.add("APP_IMAGE", "{{ image }}".to_owned());
+        environment.add("TOOLS_PATH", "/opt/{{ tag }}".to_owned());
+        scope.process_raw_env_vars(&environment).unwrap();
+
+        // Paths are added to value defined in another file
+        let mut local_environment = RawEnvironment::default();
+        local_environment.add(
+            "TOOLS_PATH",
+            EnvValue::Modify(PathsModification {
+                prepend: vec!["/srv/{{ version }}/bin".to_owned()],
+                separator: Some(":".to_owned()),
+                ..Default::default()
+            }),
+        );
+        local_environment.origin = Origin::file(&Location::None, None, "environment");
+        scope.process_raw_env_vars(&local_environment).unwrap();
+        assert_eq!(
+            scope.get_env_var("TOOLS_PATH"),
+            Some(&"/srv/1.0/bin:/opt/v1.0".to_owned())
+        );
+
+        let mut overrides = RawVariables::default();
+        overrides.add("version", variables::ValueKind::String("2.0".to_owned()));
+        overrides.origin = Origin::CommandLine;
+        let changed = scope.process_override_vars(&overrides).unwrap();
+        scope.rerender_env_vars(&environment, &changed).unwrap();
+        scope
+            .rerender_env_vars(&local_environment, &changed)
+            .unwrap();
+
+        let get = |name: &str| {
+            scope
+                .find_var(&VariableKey::try_from(name.to_string()).unwrap())
+                .unwrap()
+                .get_rendered_value()
+        };
+        assert_eq!(get("tag"), "v2.0");
+        assert_eq!(get("image"), "app:v2.0");
+        assert_eq!(get("template +raw"), "{{ version }}");
+        assert_eq!(scope.get_env_var("APP_IMAGE"), Some(&"app:v2.0".to_owned()));
+        assert_eq!(
+            scope.get_env_var("TOOLS_PATH"),
+            Some(&"/srv/2.0/bin:/opt/v2.0".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_unset_and_modify_env() {
+        env::set_var("DEVRC_TEST_UNSET", "inherited");
+        env::set_var("DEVRC_TEST_PATHS", "/usr/bin");
+
+        let scope = Rc::new(RefCell::new(Scope::default()));
+        let environment: RawEnvironment<EnvValue> = serde_yaml::from_str(
+            r#"
+DEVRC_TEST_UNSET: ~
+DEVRC_TEST_PATHS:
+  prepend: ./bin
+  append: ["/opt/bin"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            environment.vars.get("DEVRC_TEST_UNSET"),
+            Some(&EnvValue::Unset)
+        );
+        scope
+            .borrow_mut()
+            .process_raw_env_vars(&environment)
+            .unwrap();
+
+        let mut child = child_scope(Rc::clone(&scope), "task");
+        let environment: RawEnvironment<EnvValue> = serde_yaml::from_str(
+            r#"
+DEVRC_TEST_PATHS:
+  prepend: ./node_modules/.bin
+  separator: ","
+"#,
+        )
+        .unwrap();
+        child.process_raw_env_vars(&environment).unwrap();
+
+        let execution_scope = child.compute_execution_scope().unwrap();
+        assert_eq!(execution_scope.get_env_var("DEVRC_TEST_UNSET"), None);
+        assert!(execution_scope
+            .environment_unset
+            .contains("DEVRC_TEST_UNSET"));
+        assert_eq!(
+            execution_scope.get_env_var("DEVRC_TEST_PATHS"),
+            Some(&format!(
+                "./node_modules/.bin,./bin{0}/usr/bin{0}/opt/bin",
+                PATH_SEPARATOR
+            ))
+        );
+    }
+
+    #[test]
+    fn test_rerender_dependents() {
+        let mut scope = Scope::default();
+        let get = |scope: &Scope, name: &str| {
+            scope
+                .find_var(&VariableKey::try_from(name.to_string()).unwrap())
+                .unwrap()
+                .get_rendered_value()
+        };
+
+        let mut variables = RawVariables::default();
+        variables.add("version", variables::ValueKind::String("1.0".to_owned()));
+        variables.add(
+            "tag",
+            variables::ValueKind::String("v{{ version }}".to_owned()),
+        );
+        variables.add(
+            "images",
+            variables::ValueKind::List(vec![serde_yaml::Value::from("app:{{ tag }}")]),
+        );
+        scope.process_raw_vars(&variables).unwrap();
+
+        let mut overrides = RawVariables::default();
+        overrides.add(
+            "version",
+            variables::ValueKind::String("{{ version }}.1".to_owned()),
+        );
+        scope.process_raw_vars(&overrides).unwrap();
+
+        assert_eq!(get(&scope, "version"), "1.0.1");
+        assert_eq!(get(&scope, "tag"), "v1.0.1");
+        assert_eq!(get(&scope, "images"), r#"["app:v1.0.1"]"#);
+
+        // Variables are rendered again after all variables of file are added
+        let mut variables = RawVariables::default();
+        variables.add(
+            "greeting",
+            variables::ValueKind::String("hello {{ name | default(value='nobody') }}".to_owned()),
+        );
+        variables.add("name", variables::ValueKind::String("world".to_owned()));
+        scope.process_raw_vars(&variables).unwrap();
+
+        assert_eq!(get(&scope, "greeting"), "hello world");
+
+        let mut overrides = RawVariables::default();
+        overrides.add(
+            "version",
+            variables::ValueKind::String("{{ tag }}-rc".to_owned()),
+        );
+        match scope.process_raw_vars(&overrides) {
+            Err(DevrcError::CircularVariables(names)) => {
+                assert_eq!(names, vec!["version", "tag", "version"])
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        // Variables of the same file are checked before rendering
+        let mut variables = RawVariables::default();
+        variables.add("a", variables::ValueKind::String("x{{ b }}".to_owned()));
+        variables.add("b", variables::ValueKind::String("y{{ a }}".to_owned()));
+        match Scope::default().process_raw_vars(&variables) {
+            Err(DevrcError::CircularVariables(names)) => assert_eq!(names, vec!["a", "b", "a"]),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
 }
@@ -0,0 +1,171 @@
+use crate::errors::{DevrcError, DevrcResult};
+
+/// Marks the end of task arguments
+pub const ARGUMENTS_END: &str = "--";
+
+/// Separate chained tasks calls
+pub const TASKS_SEPARATORS: [&str; 2] = ["+", "::"];
+
+/// Prefix of named argument `--param=value`
+pub const NAMED_ARGUMENT_PREFIX: &str = "--";
+
+/// Tokens of command line which belong to one or several tasks calls
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CallsGroup {
+    /// Task name followed by arguments
+    pub tokens: Vec<String>,
+
+    /// Tokens after `--`
+    pub rest: Vec<String>,
+
+    /// Tokens after task name are arguments of this task only.
+    /// Otherwise arguments and next tasks names are guessed.
+    pub explicit: bool,
+}
+
+impl CallsGroup {
+    pub fn get_task_name(&self) -> DevrcResult<&String> {
+        self.tokens
+            .first()
+            .ok_or(DevrcError::TaskArgumentsParsingError)
+    }
+
+    pub fn get_arguments(&self) -> &[String] {
+        self.tokens.get(1..).unwrap_or_default()
+    }
+}
+
+pub fn is_tasks_separator(value: &str) -> bool {
+    TASKS_SEPARATORS.contains(&value)
+}
+
+/// Split command line `task1 arg1 -- + task2 arg2 :: task3`
+/// into groups of tasks calls
+///
+/// Groups are explicit if command line contains tasks separators
+/// or group contains `--`.
+pub fn parse_command_line(values: &[String]) -> DevrcResult<Vec<CallsGroup>> {
+    let has_separators = values.iter().any(|value| is_tasks_separator(value));
+
+    let mut groups = Vec::new();
+    let mut current = CallsGroup {
+        explicit: has_separators,
+        ..Default::default()
+    };
+    let mut arguments_ended = false;
+
+    for value in values {
+        if is_tasks_separator(value) {
+            current.get_task_name()?;
+            groups.push(current);
+            current = CallsGroup {
+                explicit: has_separators,
+                ..Default::default()
+            };
+            arguments_ended = false;
+        } else if arguments_ended {
+            current.rest.push(value.to_string());
+        } else if value == ARGUMENTS_END {
+            current.get_task_name()?;
+            arguments_ended = true;
+            current.explicit = true;
+        } else {
+            current.tokens.push(value.to_string());
+        }
+    }
+
+    if !current.tokens.is_empty() || has_separators || arguments_ended {
+        current.get_task_name()?;
+        groups.push(current);
+    }
+
+    Ok(groups)
+}
+
+/// Parse named argument `--name=value`
+pub fn parse_named_argument(value: &str) -> Option<(&str, &str)> {
+    let (name, value) = value.strip_prefix(NAMED_ARGUMENT_PREFIX)?.split_once('=')?;
+
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> DevrcResult<Vec<CallsGroup>> {
+        parse_command_line(
+            &input
+                .split_whitespace()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn group(tokens: &[&str], rest: &[&str], explicit: bool) -> CallsGroup {
+        CallsGroup {
+            tokens: tokens.iter().map(|value| value.to_string()).collect(),
+            rest: rest.iter().map(|value| value.to_string()).collect(),
+            explicit,
+        }
+    }
+
+    #[test]
+    fn test_implicit_calls() {
+        assert_eq!(parse("").unwrap(), vec![]);
+        assert_eq!(
+            parse("task1 arg1 task2").unwrap(),
+            vec![group(&["task1", "arg1", "task2"], &[], false)]
+        );
+    }
+
+    #[test]
+    fn test_separated_calls() {
+        assert_eq!(
+            parse("task1 arg1 + task2 --name=value :: task3").unwrap(),
+            vec![
+                group(&["task1", "arg1"], &[], true),
+                group(&["task2", "--name=value"], &[], true),
+                group(&["task3"], &[], true)
+            ]
+        );
+
+        for input in ["+ task1", "task1 + + task2", "task1 ::", "-- task1"] {
+            match parse(input) {
+                Err(DevrcError::TaskArgumentsParsingError) => {}
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_arguments_end() {
+        assert_eq!(
+            parse("task1 task2 --").unwrap(),
+            vec![group(&["task1", "task2"], &[], true)]
+        );
+        assert_eq!(
+            parse("task1 arg1 -- extra -- + task2 arg2").unwrap(),
+            vec![
+                group(&["task1", "arg1"], &["extra", "--"], true),
+                group(&["task2", "arg2"], &[], true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_named_argument() {
+        assert_eq!(
+            parse_named_argument("--name=value"),
+            Some(("name", "value"))
+        );
+        assert_eq!(parse_named_argument("--name=a=b"), Some(("name", "a=b")));
+        assert_eq!(parse_named_argument("--name="), Some(("name", "")));
+        assert_eq!(parse_named_argument("--=value"), None);
+        assert_eq!(parse_named_argument("--name"), None);
+        assert_eq!(parse_named_argument("name=value"), None);
+    }
+}
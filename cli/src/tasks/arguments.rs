@@ -1,13 +1,35 @@
+use std::env;
+
 use indexmap::IndexMap;
 
 use crate::{
     devrcfile::Devrcfile,
     errors::{DevrcError, DevrcResult},
+    input,
+};
+
+use super::{
+    args_parser::parse_named_argument,
+    params::{parse_param_name, Arity, ParamValue},
+    TaskKind,
 };
 
-use super::{params::ParamValue, TaskKind};
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgumentValue {
+    Single(String),
+    /// Values of variadic parameter
+    List(Vec<String>),
+}
+
+impl From<String> for ArgumentValue {
+    fn from(value: String) -> Self {
+        ArgumentValue::Single(value)
+    }
+}
+
+pub type TaskArguments = IndexMap<String, (ArgumentValue, ParamValue)>;
 
-pub type TaskArguments = IndexMap<String, (String, ParamValue)>;
+type NamedArguments = IndexMap<String, Vec<String>>;
 
 pub fn strip_arg_name(input: String, param_name: &str) -> DevrcResult<String> {
     let prefix = format!("{:}=", param_name);
@@ -30,57 +52,177 @@ pub fn is_argument(devrcfile: &Devrcfile, value: &str) -> bool {
     }
 }
 
+/// Ask user to enter parameter value
+pub fn ask_argument(name: &str, param: &ParamValue, devrcfile: &Devrcfile) -> DevrcResult<String> {
+    let prompt = param.get_prompt(name);
+    let designer = devrcfile.designer.parameter_name();
+
+    loop {
+        let value = if param.is_hidden() {
+            input::read_hidden_line(&prompt, &designer)?
+        } else {
+            input::read_line(&prompt, &designer)?
+        };
+
+        match (value.is_empty(), param.get_default()) {
+            (false, _) => return param.validate(name, &value),
+            (true, Some(default)) => return Ok(default.clone()),
+            (true, None) => continue,
+        }
+    }
+}
+
+/// Check that missing arguments can be asked from user
+pub fn is_input_allowed(devrcfile: &Devrcfile) -> bool {
+    !devrcfile.config.no_input && input::is_interactive()
+}
+
+/// Get parameter value from process environment or loaded environment variables
+fn get_env_argument(
+    name: &str,
+    param: &ParamValue,
+    devrcfile: &Devrcfile,
+) -> DevrcResult<Option<String>> {
+    let env_name = match param.get_env() {
+        Some(env_name) => env_name,
+        None => return Ok(None),
+    };
+
+    env::var(env_name)
+        .ok()
+        .or_else(|| devrcfile.scope.borrow().get_env_var(env_name).cloned())
+        .filter(|value| !value.is_empty())
+        .map(|value| param.validate(name, &value))
+        .transpose()
+}
+
+/// Get value of parameter which is not passed in command line
+fn get_missing_argument(
+    name: &str,
+    param: &ParamValue,
+    devrcfile: &Devrcfile,
+) -> DevrcResult<String> {
+    if let Some(value) = get_env_argument(name, param, devrcfile)? {
+        Ok(value)
+    } else if let Some(default) = param.get_default() {
+        Ok(default.clone())
+    } else if is_input_allowed(devrcfile) {
+        ask_argument(name, param, devrcfile)
+    } else {
+        Err(DevrcError::NotEnouthArguments)
+    }
+}
+
+/// Split command line values into named and positional arguments
+///
+/// If arguments aren't separated explicitly, positional arguments end
+/// on the first value which looks like a task name and isn't taken
+/// by a required parameter.
+fn collect_arguments(
+    params: &IndexMap<String, ParamValue>,
+    parts: &[String],
+    explicit: bool,
+    devrcfile: &Devrcfile,
+) -> DevrcResult<(usize, NamedArguments, Vec<String>)> {
+    let mut named: NamedArguments = IndexMap::new();
+    let mut positional = Vec::new();
+
+    for (idx, value) in parts.iter().enumerate() {
+        if let Some((name, value)) = parse_named_argument(value) {
+            if params.keys().any(|key| parse_param_name(key).0 == name) {
+                named
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(value.to_string());
+                continue;
+            } else if explicit {
+                return Err(DevrcError::UnknownArgument(name.to_string()));
+            } else {
+                return Ok((idx, named, positional));
+            }
+        }
+
+        // Parameters which are not set by name take positional arguments in order
+        let unnamed: Vec<_> = params
+            .iter()
+            .filter(|(key, _)| !named.contains_key(parse_param_name(key).0))
+            .collect();
+        let next = unnamed.get(positional.len()).or_else(|| {
+            unnamed
+                .last()
+                .filter(|(key, _)| parse_param_name(key).1.is_variadic())
+        });
+
+        let is_taken = match next {
+            Some(_) if explicit => true,
+            Some((key, param)) => {
+                (param.is_required() && !parse_param_name(key).1.is_variadic())
+                    || is_argument(devrcfile, value)
+            }
+            None if explicit => return Err(DevrcError::UnexpectedArguments(parts[idx..].to_vec())),
+            None => false,
+        };
+
+        if !is_taken {
+            return Ok((idx, named, positional));
+        }
+        positional.push(value.to_string());
+    }
+
+    Ok((parts.len(), named, positional))
+}
+
+/// Extract arguments of given task from command line values
+///
+/// Returns number of taken values and arguments.
+/// If `explicit` is true, all values must be arguments of this task.
 pub fn extract_task_args(
     task: &TaskKind,
     parts: &[String],
+    explicit: bool,
     devrcfile: &Devrcfile,
 ) -> DevrcResult<(usize, TaskArguments)> {
     let params = task.get_parameters(parts)?;
 
+    let (taken_arguments_counter, mut named, positional) =
+        collect_arguments(&params, parts, explicit, devrcfile)?;
+    let mut positional = positional.into_iter();
+
     let mut arguments: TaskArguments = indexmap::IndexMap::new();
 
-    let mut taken_arguments_counter = 0;
-    for (idx, (key, value)) in params.iter().enumerate() {
-        match value {
-            ParamValue::Required => {
-                if let Some(value) = parts.get(idx) {
-                    taken_arguments_counter += 1;
-                    arguments.insert(
-                        key.to_string(),
-                        (
-                            strip_arg_name(value.to_string(), key)?,
-                            ParamValue::Required,
-                        ),
-                    )
-                } else {
-                    return Err(DevrcError::NotEnouthArguments);
+    for (key, param) in params.iter() {
+        let (name, arity) = parse_param_name(key);
+
+        let values = match named.remove(name) {
+            Some(values) => values,
+            None if arity.is_variadic() => positional.by_ref().collect(),
+            None => positional.next().into_iter().collect(),
+        };
+
+        let value = if arity.is_variadic() {
+            let mut values = values
+                .into_iter()
+                .map(|value| param.validate(name, &strip_arg_name(value, name)?))
+                .collect::<DevrcResult<Vec<_>>>()?;
+
+            if values.is_empty() {
+                if let Some(value) = get_env_argument(name, param, devrcfile)? {
+                    values.push(value);
+                } else if arity == Arity::OneOrMore || param.get_default().is_some() {
+                    values.push(get_missing_argument(name, param, devrcfile)?);
                 }
             }
-            ParamValue::Default(default) => {
-                if let Some(value) = parts.get(idx) {
-                    if is_argument(devrcfile, value) {
-                        taken_arguments_counter += 1;
-                        arguments.insert(
-                            key.to_string(),
-                            (
-                                strip_arg_name(value.to_string(), key)?,
-                                ParamValue::Default(default.clone()),
-                            ),
-                        )
-                    } else {
-                        arguments.insert(
-                            key.to_string(),
-                            (default.clone(), ParamValue::Default(default.clone())),
-                        )
-                    }
-                } else {
-                    arguments.insert(
-                        key.to_string(),
-                        (default.clone(), ParamValue::Default(default.clone())),
-                    )
-                }
+
+            ArgumentValue::List(values)
+        } else {
+            match values.into_iter().last() {
+                Some(value) => param.validate(name, &strip_arg_name(value, name)?)?,
+                None => get_missing_argument(name, param, devrcfile)?,
             }
+            .into()
         };
+
+        arguments.insert(name.to_string(), (value, param.clone()));
     }
 
     Ok((taken_arguments_counter, arguments))
@@ -88,7 +230,147 @@ pub fn extract_task_args(
 
 #[cfg(test)]
 mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        loader::LoadingConfig,
+        raw::devrcfile::{Kind, RawDevrcfile},
+        resolver::Location,
+    };
+
+    fn load(content: &str) -> Devrcfile {
+        let mut devrcfile = Devrcfile::default();
+        let file = RawDevrcfile::prepared_from_str(
+            content,
+            Location::LocalFile(PathBuf::from("Devrcfile")),
+            Kind::Args,
+            LoadingConfig::default(),
+        )
+        .unwrap();
+        devrcfile.add_raw_devrcfile(file, &Kind::Args).unwrap();
+        devrcfile.config.no_input = true;
+        devrcfile
+    }
+
+    fn parts(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
 
     #[test]
     fn test_name() {}
+
+    #[test]
+    fn test_variadic_arguments() {
+        let devrcfile = load(
+            r#"
+fmt target *paths: echo {{ paths }}
+test +filters: echo {{ filters }}
+other: echo
+"#,
+        );
+        let task = |name: &str| devrcfile.find_task(name).unwrap();
+
+        let (counter, args) = extract_task_args(
+            task("fmt"),
+            &parts(&["src", "main.rs", "my file.rs", "other"]),
+            false,
+            &devrcfile,
+        )
+        .unwrap();
+
+        assert_eq!(counter, 3);
+        assert_eq!(args["target"].0, ArgumentValue::Single("src".to_string()));
+        assert_eq!(
+            args["paths"].0,
+            ArgumentValue::List(parts(&["main.rs", "my file.rs"]))
+        );
+
+        let (counter, args) =
+            extract_task_args(task("fmt"), &parts(&["src", "other"]), false, &devrcfile).unwrap();
+        assert_eq!(counter, 1);
+        assert_eq!(args["paths"].0, ArgumentValue::List(Vec::new()));
+
+        match extract_task_args(task("test"), &parts(&["other"]), false, &devrcfile) {
+            Err(DevrcError::NotEnouthArguments) => {}
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_explicit_arguments() {
+        let devrcfile = load(
+            r#"
+greet name other="Bob": echo {{ name }} {{ other }}
+other: echo
+"#,
+        );
+        let task = devrcfile.find_task("greet").unwrap();
+        let single = |value: &str| ArgumentValue::Single(value.to_string());
+
+        // Value equal to task name is guessed as next task
+        let (counter, args) =
+            extract_task_args(task, &parts(&["Alice", "other"]), false, &devrcfile).unwrap();
+        assert_eq!(counter, 1);
+        assert_eq!(args["other"].0, single("Bob"));
+
+        let (counter, args) =
+            extract_task_args(task, &parts(&["Alice", "other"]), true, &devrcfile).unwrap();
+        assert_eq!(counter, 2);
+        assert_eq!(args["other"].0, single("other"));
+
+        let (counter, args) = extract_task_args(
+            task,
+            &parts(&["--other=Carol", "Alice", "other"]),
+            false,
+            &devrcfile,
+        )
+        .unwrap();
+        assert_eq!(counter, 2);
+        assert_eq!(args["name"].0, single("Alice"));
+        assert_eq!(args["other"].0, single("Carol"));
+
+        match extract_task_args(task, &parts(&["Alice", "--unknown=1"]), true, &devrcfile) {
+            Err(DevrcError::UnknownArgument(name)) => assert_eq!(name, "unknown"),
+            _ => unreachable!(),
+        }
+
+        match extract_task_args(task, &parts(&["a", "b", "c"]), true, &devrcfile) {
+            Err(DevrcError::UnexpectedArguments(values)) => assert_eq!(values, parts(&["c"])),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_env_arguments() {
+        env::set_var("DEVRC_TEST_PARAM_REPLICAS", "5");
+
+        let devrcfile = load(
+            r#"
+environment:
+  DEVRC_TEST_PARAM_TARGET: staging
+
+deploy:
+  exec: echo {{ target }} {{ replicas }}
+  params:
+    target:
+      env: DEVRC_TEST_PARAM_TARGET
+    replicas:
+      type: int
+      default: 1
+      env: DEVRC_TEST_PARAM_REPLICAS
+"#,
+        );
+        let task = devrcfile.find_task("deploy").unwrap();
+        let single = |value: &str| ArgumentValue::Single(value.to_string());
+
+        let (_, args) = extract_task_args(task, &[], false, &devrcfile).unwrap();
+        assert_eq!(args["target"].0, single("staging"));
+        assert_eq!(args["replicas"].0, single("5"));
+
+        let (_, args) =
+            extract_task_args(task, &parts(&["production", "3"]), false, &devrcfile).unwrap();
+        assert_eq!(args["target"].0, single("production"));
+        assert_eq!(args["replicas"].0, single("3"));
+    }
 }
@@ -1,11 +1,17 @@
 use crate::{
     config::Config,
-    environment::RawEnvironment,
-    errors::DevrcResult,
+    env_file::EnvFiles,
+    environment::{EnvValue, RawEnvironment},
+    errors::{DevrcError, DevrcResult},
     evaluate::Evaluatable,
+    input,
     interpreter::{shebang::ShebangDetector, InterpreterKind},
+    loader::LoadingConfig,
+    origin::Origin,
+    resolver::Location,
     scope::{child_scope, Scope},
     variables::RawVariables,
+    vars_file::VarsFiles,
 };
 use std::{cell::RefCell, rc::Rc};
 
@@ -13,7 +19,7 @@ use devrc_core::workshop::Designer;
 use serde::Deserialize;
 
 use super::{
-    arguments::TaskArguments,
+    arguments::{ArgumentValue, TaskArguments},
     exec::ExecKind,
     params::{ParamValue, Params},
     result::TaskResult,
@@ -39,7 +45,15 @@ pub struct ComplexCommand {
     variables: RawVariables,
 
     #[serde(default)]
-    environment: RawEnvironment<String>,
+    environment: RawEnvironment<EnvValue>,
+
+    /// Data files which keys become task variables
+    #[serde(default)]
+    vars_file: VarsFiles,
+
+    /// Env files loaded into task scope
+    #[serde(default)]
+    env_file: EnvFiles,
 
     #[serde(default)]
     params: Params,
@@ -53,6 +67,17 @@ pub struct ComplexCommand {
 
     #[serde(default)]
     pub subtasks: Vec<SubtaskCall>,
+
+    env_clean: Option<bool>,
+
+    env_passthrough: Option<Vec<String>>,
+
+    /// Message to ask user before task execution
+    confirm: Option<String>,
+
+    /// Devrcfile where task is defined
+    #[serde(skip)]
+    location: Location,
 }
 
 impl ComplexCommand {
@@ -66,6 +91,54 @@ impl ComplexCommand {
         self.params.merge(params)
     }
 
+    pub fn setup_loading(&mut self, location: &Location, config: &LoadingConfig) {
+        self.location = location.clone();
+        self.variables.setup_loading(location, config);
+        self.vars_file.setup_loading(location, config);
+        self.env_file.setup_loading(location, config);
+
+        for subtask in self.subtasks.iter_mut() {
+            subtask.variables.setup_loading(location, config);
+            subtask.env_file.setup_loading(location, config);
+        }
+    }
+
+    pub fn setup_origin(&mut self, location: &Location, name: &str) {
+        self.variables.origin = Origin::file(location, Some(name), "variables");
+        self.environment.origin = Origin::file(location, Some(name), "environment");
+        self.vars_file.origin = Origin::file(location, Some(name), "vars_file");
+        self.env_file.origin = Origin::file(location, Some(name), "env_file");
+
+        for subtask in self.subtasks.iter_mut() {
+            let section = format!("subtask `{}` variables", subtask.name);
+            subtask.variables.origin = Origin::file(location, Some(name), &section);
+
+            let section = format!("subtask `{}` environment", subtask.name);
+            subtask.environment.origin = Origin::file(location, Some(name), &section);
+
+            let section = format!("subtask `{}` env_file", subtask.name);
+            subtask.env_file.origin = Origin::file(location, Some(name), &section);
+        }
+    }
+
+    /// Computable variables use task options and interpreter if they are defined
+    pub fn setup_execution(
+        &mut self,
+        config: &Config,
+        registry: &Rc<RefCell<ExecutionPluginManager>>,
+    ) {
+        let mut config = self.get_config(config);
+        if let Some(interpreter) = &self.interpreter {
+            config.interpreter = interpreter.clone();
+        }
+
+        self.variables.setup_execution(&config, registry);
+
+        for subtask in self.subtasks.iter_mut() {
+            subtask.variables.setup_execution(&config, registry);
+        }
+    }
+
     pub fn format_help(&self) -> &str {
         if let Some(value) = &self.desc {
             value
@@ -78,6 +151,10 @@ impl ComplexCommand {
         self.params.format_help_string(designer)
     }
 
+    pub fn format_parameters_details(&self, designer: &Designer) -> DevrcResult<Vec<String>> {
+        self.params.format_details_string(designer)
+    }
+
     pub fn get_interpreter(&self, config: &Config) -> InterpreterKind {
         if let Some(value) = &self.interpreter {
             value.clone()
@@ -86,6 +163,60 @@ impl ComplexCommand {
         }
     }
 
+    /// Get config with task specific execution options
+    pub fn get_config(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+
+        if let Some(value) = self.env_clean {
+            config.env_clean = value;
+        }
+
+        if let Some(value) = &self.env_passthrough {
+            config.env_passthrough = value.clone();
+        }
+        config
+    }
+
+    /// Show rendered confirmation message and abort task if user doesn't confirm
+    pub fn confirm(
+        &self,
+        parent_scope: Rc<RefCell<Scope>>,
+        args: &TaskArguments,
+        rest: &[String],
+        config: &Config,
+        designer: &Designer,
+    ) -> DevrcResult<()> {
+        let template = match &self.confirm {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+
+        // Preliminary scope, scope of execution is created after
+        // dependencies are executed to see variables they set
+        let mut scope = self.get_scope(parent_scope, args)?;
+        scope.args = rest.to_vec();
+
+        let execution_scope = scope.compute_execution_scope()?;
+        let message = execution_scope.mask(&template.evaluate("confirm", &execution_scope)?);
+
+        if config.dry_run || config.assume_yes {
+            config
+                .log_level
+                .debug(&format!("{} [y/N]: y", message), &designer.banner());
+            return Ok(());
+        }
+
+        if !input::is_interactive() {
+            return Err(DevrcError::NotInteractive);
+        }
+
+        if input::confirm(&message, designer)? {
+            Ok(())
+        } else {
+            Err(DevrcError::TaskNotConfirmed)
+        }
+    }
+
     pub fn perform_code(
         &self,
         interpreter: &InterpreterKind,
@@ -95,7 +226,9 @@ impl ComplexCommand {
         config: &Config,
         designer: &Designer,
     ) -> DevrcResult<()> {
-        config.log_level.info(code, &designer.command());
+        config
+            .log_level
+            .info(&local_scope.mask(code), &designer.command());
 
         if !config.dry_run {
             if let Some(interpreter) = code.get_interpreter_from_shebang() {
@@ -120,6 +253,7 @@ impl ComplexCommand {
     ) -> DevrcResult<TaskResult> {
         let local_scope = self.compute_execution_scope(parent_scope, args)?;
         let interpreter = self.get_interpreter(config);
+        let config = &self.get_config(config);
 
         match &self.exec {
             ExecKind::Empty => {}
@@ -169,18 +303,51 @@ impl ComplexCommand {
         args: &TaskArguments,
     ) -> DevrcResult<Scope> {
         let mut scope = child_scope(parent_scope, self.name.clone().unwrap_or_default().as_ref());
+        scope.directory = self.location.get_directory();
+
+        if !self.vars_file.is_empty() {
+            scope.process_loaded_vars(&self.vars_file.load()?, &self.vars_file.origin)?;
+        }
+
+        for (key, (value, param)) in args {
+            match value {
+                ArgumentValue::Single(value) => scope.process_binding(key, value)?,
+                ArgumentValue::List(values) => scope.process_list_binding(key, values)?,
+            }
 
-        // TODO: here devrc can ask user input
-        for (key, (value, _)) in args {
-            scope.process_binding(key, value)?;
+            // Values of hidden parameters are masked in output
+            if param.is_hidden() {
+                match value {
+                    ArgumentValue::Single(value) => scope.add_secret(value)?,
+                    ArgumentValue::List(values) => {
+                        scope.process_secrets(&values.iter().cloned().collect())?
+                    }
+                }
+            }
         }
 
         scope.process_raw_vars(&self.variables)?;
+
+        if !self.env_file.is_empty() {
+            scope.process_loaded_env(&self.env_file.load()?, &self.env_file.origin)?;
+        }
         scope.process_raw_env_vars(&self.environment)?;
 
         Ok(scope)
     }
 
+    pub fn get_variables(&self) -> &RawVariables {
+        &self.variables
+    }
+
+    pub fn get_environment(&self) -> &RawEnvironment<EnvValue> {
+        &self.environment
+    }
+
+    pub fn get_vars_file(&self) -> &VarsFiles {
+        &self.vars_file
+    }
+
     pub fn get_parameters(
         &self,
         _parts: &[String],
@@ -205,10 +372,16 @@ where
             example: None,
             variables: RawVariables::default(),
             environment: RawEnvironment::default(),
+            vars_file: VarsFiles::default(),
+            env_file: EnvFiles::default(),
             params: Params::default(),
             deps: Vec::new(),
             interpreter: None,
             subtasks: Vec::new(),
+            env_clean: None,
+            env_passthrough: None,
+            confirm: None,
+            location: Location::None,
         }
     }
 }
@@ -222,10 +395,16 @@ impl From<ExecKind> for ComplexCommand {
             example: None,
             variables: RawVariables::default(),
             environment: RawEnvironment::default(),
+            vars_file: VarsFiles::default(),
+            env_file: EnvFiles::default(),
             params: Params::default(),
             deps: Vec::new(),
             interpreter: None,
             subtasks: Vec::new(),
+            env_clean: None,
+            env_passthrough: None,
+            confirm: None,
+            location: Location::None,
         }
     }
 }
@@ -239,10 +418,16 @@ impl Default for ComplexCommand {
             example: None,
             variables: RawVariables::default(),
             environment: RawEnvironment::default(),
+            vars_file: VarsFiles::default(),
+            env_file: EnvFiles::default(),
             params: Params::default(),
             deps: Vec::new(),
             interpreter: None,
             subtasks: Vec::new(),
+            env_clean: None,
+            env_passthrough: None,
+            confirm: None,
+            location: Location::None,
         }
     }
 }
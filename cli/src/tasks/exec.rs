@@ -1,8 +1,9 @@
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 #[serde(untagged)]
 pub enum ExecKind {
+    #[default]
     Empty,
     String(String),
     // Complex(indexmap::IndexMap<String, String>),
@@ -11,12 +12,6 @@ pub enum ExecKind {
 
 impl ExecKind {}
 
-impl Default for ExecKind {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 #[cfg(test)]
 mod tests {
     #[test]
@@ -10,10 +10,13 @@ use serde::de::{MapAccess, Visitor};
 use crate::{
     config::Config,
     errors::{DevrcError, DevrcResult},
+    loader::LoadingConfig,
+    resolver::Location,
     scope::Scope,
 };
 use std::rc::Rc;
 
+pub mod args_parser;
 pub mod arguments;
 pub mod complex;
 pub mod examples;
@@ -105,6 +108,13 @@ impl TaskKind {
         }
     }
 
+    pub fn format_parameters_details(&self, designer: &Designer) -> DevrcResult<Vec<String>> {
+        match self {
+            TaskKind::ComplexCommand(command) => command.format_parameters_details(designer),
+            _ => Ok(Vec::new()),
+        }
+    }
+
     pub fn is_private(&self) -> bool {
         false
     }
@@ -170,6 +180,24 @@ impl TaskKind {
         Ok(result)
     }
 
+    /// Ask user to confirm task execution if task requires it
+    pub fn confirm(
+        &self,
+        _name: &str,
+        parent_scope: Rc<RefCell<Scope>>,
+        args: &TaskArguments,
+        rest: &[String],
+        config: &Config,
+        designer: &Designer,
+    ) -> DevrcResult<()> {
+        match self {
+            TaskKind::ComplexCommand(command) => {
+                command.confirm(parent_scope, args, rest, config, designer)
+            }
+            _ => Ok(()),
+        }
+    }
+
     // Get list of task dependencies
     pub fn get_dependencies(&self) -> Option<&Vec<String>> {
         if let TaskKind::ComplexCommand(command) = self {
@@ -207,6 +235,31 @@ impl TaskKind {
             _ => false,
         }
     }
+
+    /// Set location of Devrcfile where task is defined
+    pub fn setup_loading(&mut self, location: &Location, config: &LoadingConfig) {
+        if let TaskKind::ComplexCommand(value) = self {
+            value.setup_loading(location, config)
+        }
+    }
+
+    /// Set location and name of task for its variables and environment variables
+    pub fn setup_origin(&mut self, location: &Location, name: &str) {
+        if let TaskKind::ComplexCommand(value) = self {
+            value.setup_origin(location, name)
+        }
+    }
+
+    /// Set options and plugins of task computable variables
+    pub fn setup_execution(
+        &mut self,
+        config: &Config,
+        registry: &Rc<RefCell<ExecutionPluginManager>>,
+    ) {
+        if let TaskKind::ComplexCommand(value) = self {
+            value.setup_execution(config, registry)
+        }
+    }
 }
 
 //#[derive(Debug, Deserialize, Clone, Default)]
@@ -286,10 +339,7 @@ impl<'de> Deserialize<'de> for Tasks {
             {
                 let mut elements: IndexMap<TaskName, Task> = IndexMap::new();
 
-                while let Some((key, value)) = match access.next_entry::<TaskName, Task>() {
-                    Ok(value) => value,
-                    Err(error) => return Err(error),
-                } {
+                while let Some((key, value)) = access.next_entry::<TaskName, Task>()? {
                     let command = match value {
                         TaskKind::Command(value) => {
                             TaskKind::ComplexCommand(ComplexCommand::from(value))
@@ -318,6 +368,52 @@ impl<'de> Deserialize<'de> for Tasks {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::tasks::arguments::ArgumentValue;
+
     #[test]
     fn test_task_execute() {}
+
+    #[test]
+    fn test_hidden_arguments_are_secrets() {
+        let task: TaskKind = serde_yaml::from_str(
+            r#"
+exec: echo "using {{ token }}"
+params:
+  token:
+    hidden: true
+  name:
+"#,
+        )
+        .unwrap();
+
+        let params = match &task {
+            TaskKind::ComplexCommand(command) => command.get_parameters(&[]).unwrap(),
+            _ => unreachable!(),
+        };
+        let mut args = TaskArguments::new();
+        args.insert(
+            "token".to_string(),
+            (
+                ArgumentValue::Single("hunter22supersecret".to_string()),
+                params["token"].clone(),
+            ),
+        );
+        args.insert(
+            "name".to_string(),
+            (
+                ArgumentValue::Single("alice".to_string()),
+                params["name"].clone(),
+            ),
+        );
+
+        let scope = task
+            .get_scope("test", Rc::new(RefCell::new(Scope::default())), &args)
+            .unwrap();
+
+        assert_eq!(
+            scope.mask("using hunter22supersecret as alice"),
+            "using *** as alice"
+        );
+    }
 }
@@ -1,16 +1,146 @@
-use std::{cmp::Ordering, convert::TryFrom};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{self, Display},
+};
 
 use devrc_core::workshop::Designer;
+use regex::Regex;
 use serde::Deserialize;
+use url::Url;
 
-use crate::errors::{DevrcError, DevrcResult};
+use crate::{
+    de::{deserialize_option_scalar, deserialize_option_scalar_list},
+    errors::{DevrcError, DevrcResult},
+    scope::SECRET_MASK,
+};
 
 use super::params_parser::parse_params_string;
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    #[default]
+    String,
+    Int,
+    Float,
+    Bool,
+    Enum,
+    Path,
+    Url,
+}
+
+impl Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ParamType::String => "string",
+            ParamType::Int => "int",
+            ParamType::Float => "float",
+            ParamType::Bool => "bool",
+            ParamType::Enum => "enum",
+            ParamType::Path => "path",
+            ParamType::Url => "url",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
 pub struct ComplexParam {
+    #[serde(default, rename = "type")]
+    pub param_type: ParamType,
+
+    #[serde(default, deserialize_with = "deserialize_option_scalar")]
     pub default: Option<String>,
+
+    #[serde(default)]
     pub desc: Option<String>,
+
+    /// Text shown when devrc asks user for the value
+    #[serde(default)]
+    pub prompt: Option<String>,
+
+    /// Don't echo user input
+    #[serde(default)]
+    pub hidden: bool,
+
+    /// Allowed values
+    #[serde(default, deserialize_with = "deserialize_option_scalar_list")]
+    pub choices: Option<Vec<String>>,
+
+    /// Regular expression which whole value must match
+    #[serde(default)]
+    pub pattern: Option<String>,
+
+    /// Environment variable which is used if argument isn't passed
+    #[serde(default)]
+    pub env: Option<String>,
+}
+
+impl ComplexParam {
+    /// Check given value and return it in normalized form
+    pub fn validate(&self, name: &str, value: &str) -> DevrcResult<String> {
+        let error = |reason: String| DevrcError::InvalidArgumentValue {
+            name: name.to_string(),
+            value: if self.hidden {
+                SECRET_MASK.to_string()
+            } else {
+                value.to_string()
+            },
+            reason,
+        };
+
+        let value = match self.param_type {
+            ParamType::String | ParamType::Enum => value.to_string(),
+            ParamType::Int => value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| error("expected an integer".to_string()))?
+                .to_string(),
+            ParamType::Float => {
+                value
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| error("expected a number".to_string()))?;
+                value.trim().to_string()
+            }
+            ParamType::Bool => match value.trim().to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => "true".to_string(),
+                "false" | "no" | "off" | "0" => "false".to_string(),
+                _ => return Err(error("expected true or false".to_string())),
+            },
+            ParamType::Path => {
+                if value.is_empty() {
+                    return Err(error("expected a path".to_string()));
+                }
+                value.to_string()
+            }
+            ParamType::Url => {
+                Url::parse(value).map_err(|err| error(format!("expected an url, {}", err)))?;
+                value.to_string()
+            }
+        };
+
+        match (&self.choices, self.param_type) {
+            (Some(choices), _) if !choices.contains(&value) => {
+                return Err(error(format!("expected one of {}", choices.join(", "))))
+            }
+            (None, ParamType::Enum) => {
+                return Err(error("enum parameter has no choices".to_string()))
+            }
+            _ => {}
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let regex = Regex::new(&format!("^(?:{})$", pattern))
+                .map_err(|err| error(format!("invalid pattern `{}`, {}", pattern, err)))?;
+            if !regex.is_match(&value) {
+                return Err(error(format!("expected value matching `{}`", pattern)));
+            }
+        }
+
+        Ok(value)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
@@ -18,7 +148,117 @@ pub struct ComplexParam {
 pub enum ParamValue {
     Required,
     Default(String),
-    // Complex(ComplexParam),
+    Complex(ComplexParam),
+}
+
+impl ParamValue {
+    pub fn get_default(&self) -> Option<&String> {
+        match self {
+            ParamValue::Required => None,
+            ParamValue::Default(default) => Some(default),
+            ParamValue::Complex(ComplexParam { default, .. }) => default.as_ref(),
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.get_default().is_none()
+    }
+
+    pub fn get_desc(&self) -> Option<&String> {
+        match self {
+            ParamValue::Complex(ComplexParam { desc, .. }) => desc.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Validate argument value according to parameter type and constraints
+    pub fn validate(&self, name: &str, value: &str) -> DevrcResult<String> {
+        match self {
+            ParamValue::Complex(param) => param.validate(name, value),
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    /// Get short description of parameter type and constraints
+    pub fn get_type_help(&self) -> Option<String> {
+        match self {
+            ParamValue::Complex(ComplexParam {
+                param_type,
+                choices,
+                pattern,
+                ..
+            }) => {
+                let mut help = param_type.to_string();
+                if let Some(choices) = choices {
+                    help = format!("{}: {}", help, choices.join("|"));
+                }
+                if let Some(pattern) = pattern {
+                    help = format!("{}, pattern: {}", help, pattern);
+                }
+                Some(help)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_env(&self) -> Option<&String> {
+        match self {
+            ParamValue::Complex(ComplexParam { env, .. }) => env.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        matches!(self, ParamValue::Complex(ComplexParam { hidden: true, .. }))
+    }
+
+    /// Get text which is shown to user when parameter value is asked
+    pub fn get_prompt(&self, name: &str) -> String {
+        let mut prompt = match self {
+            ParamValue::Complex(ComplexParam {
+                prompt: Some(prompt),
+                ..
+            }) => prompt.trim_end().to_string(),
+            _ => format!("Enter `{}`", name),
+        };
+
+        if let Some(desc) = self.get_desc() {
+            prompt = format!("{} ({})", prompt, desc);
+        }
+
+        if let Some(default) = self.get_default() {
+            prompt = format!("{} [{}]", prompt, default);
+        }
+
+        format!("{}: ", prompt)
+    }
+}
+
+/// How many arguments parameter takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Single,
+    /// `*name` collects zero or more remaining arguments
+    ZeroOrMore,
+    /// `+name` collects one or more remaining arguments
+    OneOrMore,
+}
+
+impl Arity {
+    pub fn is_variadic(&self) -> bool {
+        !matches!(self, Arity::Single)
+    }
+}
+
+/// Split parameter name into name without marker and arity
+pub fn parse_param_name(name: &str) -> (&str, Arity) {
+    if let Some(name) = name.strip_prefix('*') {
+        (name, Arity::ZeroOrMore)
+    } else if let Some(name) = name.strip_prefix('+') {
+        (name, Arity::OneOrMore)
+    } else {
+        (name, Arity::Single)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
@@ -42,11 +282,37 @@ impl Params {
     }
 
     pub fn sort(&mut self) -> DevrcResult<()> {
-        self.params.sort_by(|_, v1, _, v2| match (v1, v2) {
-            (ParamValue::Required, ParamValue::Required) => Ordering::Equal,
-            (ParamValue::Required, ParamValue::Default(_)) => Ordering::Less,
-            (ParamValue::Default(_), ParamValue::Required) => Ordering::Greater,
-            (ParamValue::Default(_), ParamValue::Default(_)) => Ordering::Equal,
+        let mut variadic = 0;
+        for key in self.params.keys() {
+            let (name, arity) = parse_param_name(key);
+            if name.contains(['*', '+']) || name.is_empty() {
+                return Err(DevrcError::InvalidParams);
+            }
+            if arity.is_variadic() {
+                variadic += 1;
+            }
+        }
+
+        // Only one parameter can collect remaining arguments
+        if variadic > 1 {
+            return Err(DevrcError::InvalidParams);
+        }
+
+        self.params.sort_by(|k1, v1, k2, v2| {
+            match (
+                parse_param_name(k1).1.is_variadic(),
+                parse_param_name(k2).1.is_variadic(),
+            ) {
+                (false, true) => return Ordering::Less,
+                (true, false) => return Ordering::Greater,
+                _ => {}
+            };
+
+            match (v1.is_required(), v2.is_required()) {
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                _ => Ordering::Equal,
+            }
         });
 
         // self.params = sorted;
@@ -70,7 +336,22 @@ impl Params {
         self.params = params.params;
 
         self.sort()?;
+        self.validate_defaults()?;
+
+        Ok(())
+    }
 
+    /// Check that default values match parameters types and constraints,
+    /// templates are checked only after rendering
+    pub fn validate_defaults(&self) -> DevrcResult<()> {
+        for (key, value) in &self.params {
+            match value.get_default() {
+                Some(default) if !default.contains("{{") && !default.contains("{%") => {
+                    value.validate(parse_param_name(key).0, default)?;
+                }
+                _ => {}
+            }
+        }
         Ok(())
     }
 
@@ -78,14 +359,14 @@ impl Params {
         let mut parts = Vec::new();
 
         for (key, value) in self.params.iter() {
-            match value {
-                ParamValue::Required => parts.push(format!(
+            match value.get_default() {
+                None => parts.push(format!(
                     "{}{}{}",
                     designer.parameter_name().prefix(),
                     key,
                     designer.parameter_name().suffix()
                 )),
-                ParamValue::Default(default) => {
+                Some(default) => {
                     let help = format!(
                         "{}{}{}=\"{}{}{}\"",
                         designer.parameter_name().prefix(),
@@ -104,6 +385,41 @@ impl Params {
         Ok(parts.join(" "))
     }
 
+    /// Get lines with parameters types and descriptions
+    pub fn format_details_string(&self, designer: &Designer) -> DevrcResult<Vec<String>> {
+        let mut lines = Vec::new();
+
+        for (key, value) in self.params.iter() {
+            let mut details = Vec::new();
+
+            if let Some(type_help) = value.get_type_help() {
+                details.push(format!("({})", type_help));
+            }
+
+            if let Some(desc) = value.get_desc() {
+                details.push(desc.to_string());
+            }
+
+            if let Some(env) = value.get_env() {
+                details.push(format!("[env: {}]", env));
+            }
+
+            if details.is_empty() {
+                continue;
+            }
+
+            lines.push(format!(
+                "{}{}{} {}",
+                designer.parameter_name().prefix(),
+                key,
+                designer.parameter_name().suffix(),
+                details.join(" ")
+            ));
+        }
+
+        Ok(lines)
+    }
+
     // pub fn evaluate(&self, parent_scope: &Scope) -> DevrcResult<indexmap::IndexMap<String, String>>{
     //     let mut local_scope = parent_scope.clone();
     //     let mut vars = indexmap::IndexMap::new();
@@ -134,3 +450,143 @@ impl Default for Params {
         Self { params }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complex_params() {
+        let content = r#"
+name:
+other: "Alice"
+token:
+  desc: "API token"
+  prompt: "Token"
+  hidden: true
+count:
+  default: "3"
+"#;
+        let mut params: Params = serde_yaml::from_str(content).unwrap();
+        params.sort().unwrap();
+
+        assert_eq!(
+            params.params.keys().collect::<Vec<_>>(),
+            vec!["name", "token", "other", "count"]
+        );
+        assert!(params.params["token"].is_hidden());
+        assert_eq!(params.params["count"].get_default(), Some(&"3".to_string()));
+        assert_eq!(
+            params.params["token"].get_prompt("token"),
+            "Token (API token): "
+        );
+        assert_eq!(
+            params.params["count"].get_prompt("count"),
+            "Enter `count` [3]: "
+        );
+    }
+
+    #[test]
+    fn test_typed_params() {
+        let content = r#"
+count:
+  type: int
+  default: 3
+  desc: "Number of retries"
+ratio:
+  type: float
+verbose:
+  type: bool
+env:
+  type: enum
+  choices: [dev, prod]
+home:
+  type: url
+version:
+  pattern: "[0-9]+\\.[0-9]+"
+target:
+  env: DEPLOY_TARGET
+"#;
+        let params: Params = serde_yaml::from_str(content).unwrap();
+        let param = |name: &str| params.params[name].clone();
+
+        assert_eq!(param("count").get_default(), Some(&"3".to_string()));
+        assert_eq!(param("count").validate("count", "10").unwrap(), "10");
+        assert_eq!(param("ratio").validate("ratio", "0.5").unwrap(), "0.5");
+        assert_eq!(param("verbose").validate("verbose", "Yes").unwrap(), "true");
+        assert_eq!(param("env").validate("env", "prod").unwrap(), "prod");
+        assert_eq!(param("version").validate("version", "1.2").unwrap(), "1.2");
+        param("home")
+            .validate("home", "https://example.com")
+            .unwrap();
+
+        for (name, value) in [
+            ("count", "ten"),
+            ("ratio", "half"),
+            ("verbose", "maybe"),
+            ("env", "stage"),
+            ("home", "example"),
+            ("version", "1.2.3"),
+        ] {
+            match param(name).validate(name, value) {
+                Err(DevrcError::InvalidArgumentValue {
+                    name: error_name,
+                    value: error_value,
+                    ..
+                }) => {
+                    assert_eq!(error_name, name);
+                    assert_eq!(error_value, value);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        let token: ParamValue = serde_yaml::from_str("{type: int, hidden: true}").unwrap();
+        match token.validate("token", "s3cr3t") {
+            Err(DevrcError::InvalidArgumentValue { value, .. }) => assert_eq!(value, "***"),
+            _ => unreachable!(),
+        }
+
+        let mut invalid: Params =
+            serde_yaml::from_str("env: {type: enum, choices: [dev, prod], default: stage}")
+                .unwrap();
+        match invalid.merge(Params::default()) {
+            Err(DevrcError::InvalidArgumentValue { name, value, .. }) => {
+                assert_eq!(name, "env");
+                assert_eq!(value, "stage");
+            }
+            _ => unreachable!(),
+        }
+
+        let mut templated: Params =
+            serde_yaml::from_str("count: {type: int, default: \"{{ replicas }}\"}").unwrap();
+        templated.merge(Params::default()).unwrap();
+
+        let details = params.format_details_string(&Designer::default()).unwrap();
+        assert!(details[0].ends_with("count\u{1b}[0m (int) Number of retries"));
+        assert!(details[3].ends_with("env\u{1b}[0m (enum: dev|prod)"));
+        assert!(details[6].ends_with("target\u{1b}[0m (string) [env: DEPLOY_TARGET]"));
+    }
+
+    #[test]
+    fn test_variadic_params() {
+        let mut params = parse_params_string(r#"*paths check="no" target"#).unwrap();
+        params.sort().unwrap();
+
+        assert_eq!(
+            params.params.keys().collect::<Vec<_>>(),
+            vec!["target", "check", "*paths"]
+        );
+        assert_eq!(parse_param_name("*paths"), ("paths", Arity::ZeroOrMore));
+        assert_eq!(parse_param_name("+paths"), ("paths", Arity::OneOrMore));
+        assert_eq!(parse_param_name("paths"), ("paths", Arity::Single));
+
+        for input in ["*paths +filters", "pa*ths", "**paths"] {
+            let mut params = parse_params_string(input).unwrap();
+            match params.sort() {
+                Err(DevrcError::InvalidParams) => {}
+                _ => unreachable!(),
+            }
+        }
+    }
+}
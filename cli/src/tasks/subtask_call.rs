@@ -1,14 +1,22 @@
 use serde::Deserialize;
 
-use crate::{environment::RawEnvironment, variables::RawVariables};
+use crate::{
+    env_file::EnvFiles,
+    environment::{EnvValue, RawEnvironment},
+    variables::RawVariables,
+};
 
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct SubtaskCall {
     pub name: String,
 
     #[serde(default)]
-    pub environment: RawEnvironment<String>,
+    pub environment: RawEnvironment<EnvValue>,
 
     #[serde(default)]
     pub variables: RawVariables,
+
+    /// Env files loaded into subtask scope
+    #[serde(default)]
+    pub env_file: EnvFiles,
 }
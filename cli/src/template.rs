@@ -1,54 +1,235 @@
-use std::convert::TryInto;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    path::PathBuf,
+    sync::Mutex,
+};
 
-use tera::{Context, Tera};
+use serde::Deserialize;
+use tera::{Context, Template, Tera};
 
 use crate::{
+    de::deserialize_some,
+    env_file::LocalFileImport,
     errors::{DevrcError, DevrcResult},
+    loader::LoadingConfig,
+    resolver::{Location, PathResolve},
     scope::Scope,
+    template_functions::{self, BaseDirectory},
 };
 
-pub fn render_string(name: &str, template: &str, scope: &Scope) -> DevrcResult<String> {
-    let context: Context = scope.try_into()?;
+/// Named template which can be included or imported by other templates
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum TemplateSource {
+    Inline(String),
+    File {
+        file: PathBuf,
+
+        #[serde(default)]
+        path_resolve: PathResolve,
+
+        #[serde(default, deserialize_with = "deserialize_some")]
+        checksum: Option<String>,
+    },
+}
 
-    let _autoescape = true;
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RawTemplates {
+    #[serde(flatten)]
+    pub templates: indexmap::IndexMap<String, TemplateSource>,
+}
+
+impl RawTemplates {
+    /// Read templates files relative to Devrcfile location
+    pub fn load(
+        &self,
+        location: &Location,
+        config: &LoadingConfig,
+    ) -> DevrcResult<indexmap::IndexMap<String, String>> {
+        let mut result = indexmap::IndexMap::new();
 
-    // TODO: pass tera as input parameter
-    let mut tera = Tera::default();
+        for (name, source) in &self.templates {
+            let content = match source {
+                TemplateSource::Inline(content) => content.clone(),
+                TemplateSource::File {
+                    file,
+                    path_resolve,
+                    checksum,
+                } => LocalFileImport {
+                    file: file.clone(),
+                    path_resolve: path_resolve.clone(),
+                    checksum: checksum.clone(),
+                    ..Default::default()
+                }
+                .get_content(location.clone(), config.clone())?,
+            };
+            result.insert(name.clone(), content);
+        }
+        Ok(result)
+    }
+}
 
-    // if autoescape {
-    //     tera.autoescape_on(vec![ONE_OFF_TEMPLATE_NAME]);
-    // }
+/// Template engine with devrc functions and already parsed templates
+pub struct TemplateEngine {
+    tera: Tera,
+    /// Sources of parsed templates by names
+    sources: HashMap<String, String>,
+    /// Names of templates from `templates` sections
+    named: HashSet<String>,
+    /// Directory which relative paths of functions are resolved against
+    directory: BaseDirectory,
+}
+
+lazy_static! {
+    static ref ENGINE: Mutex<TemplateEngine> = Mutex::new(TemplateEngine::new());
+}
+
+impl TemplateEngine {
+    pub fn new() -> Self {
+        let directory = BaseDirectory::default();
+        let mut tera = Tera::default();
+        template_functions::register(&mut tera, &directory);
+
+        Self {
+            tera,
+            sources: HashMap::new(),
+            named: HashSet::new(),
+            directory,
+        }
+    }
+
+    /// Set directory which relative paths of functions are resolved against
+    pub fn set_directory(&mut self, directory: Option<PathBuf>) -> DevrcResult<()> {
+        *self
+            .directory
+            .write()
+            .map_err(|_| DevrcError::RuntimeError)? = directory;
+        Ok(())
+    }
 
-    tera.add_raw_template(name, template)?;
-    let result = tera.render(name, &context);
-    tera.templates.remove(name);
+    /// Add templates which can be included or imported by name.
+    ///
+    /// None of them is added if any of them is broken.
+    pub fn add_templates(
+        &mut self,
+        templates: &indexmap::IndexMap<String, String>,
+    ) -> DevrcResult<()> {
+        let previous = self.get_templates(templates.keys());
+        if let Err(error) = self.tera.add_raw_templates(
+            templates
+                .iter()
+                .map(|(name, content)| (name.as_str(), content.as_str())),
+        ) {
+            self.restore_templates(previous);
+            return Err(error.into());
+        }
+
+        for name in templates.keys() {
+            self.sources.remove(name);
+            self.named.insert(name.clone());
+        }
+        Ok(())
+    }
+
+    /// Render template, it's parsed only if it wasn't parsed before
+    pub fn render(&mut self, name: &str, template: &str, context: &Context) -> DevrcResult<String> {
+        // Don't replace named template by task command or variable with the same name
+        let inline_name;
+        let name = if self.named.contains(name) {
+            inline_name = format!("{}:inline", name);
+            &inline_name
+        } else {
+            name
+        };
+
+        if self.sources.get(name).map(|source| source.as_str()) == Some(template) {
+            return Ok(self.tera.render(name, context)?);
+        }
+
+        // Broken template is removed to not break other templates
+        let previous = self.get_templates([name]);
+        let result = self
+            .tera
+            .add_raw_template(name, template)
+            .and_then(|_| self.tera.render(name, context));
+        match result {
+            Ok(rendered) => {
+                self.sources.insert(name.to_string(), template.to_string());
+                Ok(rendered)
+            }
+            Err(error) => {
+                self.restore_templates(previous);
+                self.sources.remove(name);
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Get parsed templates with given names to restore them later
+    fn get_templates<I, N>(&self, names: I) -> Vec<(String, Option<Template>)>
+    where
+        I: IntoIterator<Item = N>,
+        N: AsRef<str>,
+    {
+        names
+            .into_iter()
+            .map(|name| {
+                let name = name.as_ref();
+                (name.to_string(), self.tera.templates.get(name).cloned())
+            })
+            .collect()
+    }
+
+    /// Replace templates by previous ones, templates which didn't exist are removed
+    fn restore_templates(&mut self, templates: Vec<(String, Option<Template>)>) {
+        for (name, template) in templates {
+            match template {
+                Some(template) => self.tera.templates.insert(name, template),
+                None => self.tera.templates.remove(&name),
+            };
+        }
+    }
+}
 
-    match result {
-        Ok(value) => Ok(value),
-        Err(error) => Err(error.into()), // Err(value) => {
-                                         //     // TODO: wrap Tera error
-                                         //     // println!("Render template error: {:}", value);
-                                         //     Err(DevrcError::RenderError(value))
-                                         // }
+impl Default for TemplateEngine {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// Register named templates in shared template engine
+pub fn add_templates(templates: &indexmap::IndexMap<String, String>) -> DevrcResult<()> {
+    ENGINE
+        .lock()
+        .map_err(|_| DevrcError::RuntimeError)?
+        .add_templates(templates)
+}
+
+/// Render template of scope by shared template engine
+fn render(name: &str, template: &str, context: &Context, scope: &Scope) -> DevrcResult<String> {
+    let mut engine = ENGINE.lock().map_err(|_| DevrcError::RuntimeError)?;
+    engine.set_directory(scope.get_directory())?;
+    engine.render(name, template, context)
+}
+
+pub fn render_string(name: &str, template: &str, scope: &Scope) -> DevrcResult<String> {
+    let mut context: Context = scope.try_into()?;
+    scope.insert_computed_vars(template, &mut context)?;
+
+    render(name, template, &context, scope)
+}
+
 pub fn render_multiple(
     input: indexmap::IndexMap<String, String>,
     scope: &Scope,
 ) -> DevrcResult<indexmap::IndexMap<String, String>> {
     let mut result = indexmap::IndexMap::new();
-    let _autoescape = true;
     let mut context: Context = scope.try_into()?;
 
-    let mut tera = Tera::default();
-
     for (template_name, template_value) in input.iter() {
-        tera.add_raw_template(template_name, template_value)?;
-        let rendered_template = tera
-            .render(template_name, &context)
-            .map_err(DevrcError::RenderError)?;
-        tera.templates.remove(template_name);
+        scope.insert_computed_vars(template_value, &mut context)?;
+        let rendered_template = render(template_name, template_value, &context, scope)?;
 
         context
             .try_insert(template_name, &rendered_template)
@@ -109,4 +290,98 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_named_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Devrcfile"), "").unwrap();
+        std::fs::write(dir.path().join("test_header.sh"), "set -eu").unwrap();
+
+        let templates: RawTemplates = serde_yaml::from_str(
+            r#"
+test_header.sh:
+  file: test_header.sh
+test_macros: "{% macro greet(name) %}Hello {{ name }}{% endmacro greet %}"
+"#,
+        )
+        .unwrap();
+        let loaded = templates
+            .load(
+                &Location::LocalFile(dir.path().join("Devrcfile")),
+                &LoadingConfig::default(),
+            )
+            .unwrap();
+        add_templates(&loaded).unwrap();
+
+        assert_eq!(
+            render_string(
+                "test_task",
+                r#"{% import "test_macros" as m %}{% include "test_header.sh" %}; echo {{ m::greet(name="Alice") }}"#,
+                &Scope::default()
+            )
+            .unwrap(),
+            "set -eu; echo Hello Alice"
+        );
+
+        // Command with the same name doesn't replace named template
+        assert_eq!(
+            render_string("test_macros", "other", &Scope::default()).unwrap(),
+            "other"
+        );
+        assert_eq!(
+            render_string(
+                "test_task",
+                r#"{% import "test_macros" as m %}{{ m::greet(name="Bob") }}"#,
+                &Scope::default()
+            )
+            .unwrap(),
+            "Hello Bob"
+        );
+    }
+
+    #[test]
+    fn test_broken_template_is_not_kept() {
+        assert!(render_string(
+            "broken",
+            r#"{% import "not_existing" as m %}x"#,
+            &Scope::default()
+        )
+        .is_err());
+
+        assert_eq!(
+            render_string("valid", "hello", &Scope::default()).unwrap(),
+            "hello"
+        );
+    }
+
+    #[test]
+    fn test_broken_named_templates_are_rolled_back() {
+        let mut engine = TemplateEngine::new();
+
+        let templates = indexmap::indexmap! {
+            "valid_macros".to_string() => "{% macro a() %}a{% endmacro a %}".to_string(),
+            "broken_macros".to_string() => r#"{% import "not_existing" as m %}"#.to_string(),
+        };
+        let error = engine.add_templates(&templates).unwrap_err();
+        assert!(format!("{:?}", error).contains("broken_macros"));
+
+        engine
+            .add_templates(&indexmap::indexmap! {
+                "header".to_string() => "set -eu".to_string(),
+            })
+            .unwrap();
+        assert_eq!(
+            engine
+                .render("task", r#"{% include "header" %}"#, &Context::new())
+                .unwrap(),
+            "set -eu"
+        );
+        assert!(engine
+            .render(
+                "task",
+                r#"{% import "valid_macros" as m %}{{ m::a() }}"#,
+                &Context::new()
+            )
+            .is_err());
+    }
 }
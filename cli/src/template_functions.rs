@@ -0,0 +1,374 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use tera::{Error, Result, Tera, Value};
+
+use crate::utils::shell_quote;
+
+type Args = HashMap<String, Value>;
+
+/// Directory which relative paths of functions are resolved against,
+/// current directory is used if it's not set
+pub type BaseDirectory = Arc<RwLock<Option<PathBuf>>>;
+
+/// Register devrc functions and filters
+pub fn register(tera: &mut Tera, directory: &BaseDirectory) {
+    tera.register_function("env", env_function);
+    tera.register_function("file", with_directory(directory, file_function));
+    tera.register_function("sha256", with_directory(directory, sha256_function));
+    tera.register_function("glob", with_directory(directory, glob_function));
+    tera.register_function("exists", with_directory(directory, exists_function));
+    tera.register_function("now", now_function);
+    tera.register_function("os", |_: &Args| Ok(Value::from(env::consts::OS)));
+    tera.register_function("arch", |_: &Args| Ok(Value::from(env::consts::ARCH)));
+    tera.register_function("uuid", |_: &Args| {
+        Ok(Value::from(uuid::Uuid::new_v4().to_string()))
+    });
+
+    tera.register_filter("shell_quote", shell_quote_filter);
+    tera.register_filter("path_join", path_join_filter);
+    tera.register_filter("relpath", relpath_filter);
+    tera.register_filter("semver_bump", semver_bump_filter);
+}
+
+fn get_string_arg(name: &str, args: &Args, function: &str) -> Result<Option<String>> {
+    match args.get(name) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(value) => Err(Error::msg(format!(
+            "Argument `{}` of `{}` must be a string, got `{}`",
+            name, function, value
+        ))),
+    }
+}
+
+fn get_required_arg(name: &str, args: &Args, function: &str) -> Result<String> {
+    get_string_arg(name, args, function)?
+        .ok_or_else(|| Error::msg(format!("`{}` requires `{}` argument", function, name)))
+}
+
+fn get_string_value(value: &Value, filter: &str) -> Result<String> {
+    value
+        .as_str()
+        .map(|value| value.to_string())
+        .ok_or_else(|| {
+            Error::msg(format!(
+                "`{}` filter requires a string, got `{}`",
+                filter, value
+            ))
+        })
+}
+
+/// Pass base directory to function
+fn with_directory<F>(
+    directory: &BaseDirectory,
+    function: F,
+) -> impl Fn(&Args) -> Result<Value> + Sync + Send
+where
+    F: Fn(&Args, &Path) -> Result<Value> + Sync + Send,
+{
+    let directory = Arc::clone(directory);
+    move |args| {
+        let base = match directory.read() {
+            Ok(directory) => directory.clone(),
+            Err(_) => None,
+        };
+        let base = match base {
+            Some(base) => base,
+            None => env::current_dir().map_err(|error| Error::msg(error.to_string()))?,
+        };
+        function(args, &base)
+    }
+}
+
+/// `env(name="HOME", default="")`
+fn env_function(args: &Args) -> Result<Value> {
+    let name = get_required_arg("name", args, "env")?;
+
+    match (env::var(&name), args.get("default")) {
+        (Ok(value), _) => Ok(Value::from(value)),
+        (Err(_), Some(default)) => Ok(default.clone()),
+        (Err(_), None) => Err(Error::msg(format!(
+            "Environment variable `{}` is not set",
+            name
+        ))),
+    }
+}
+
+/// `file(path="VERSION")`
+fn file_function(args: &Args, base: &Path) -> Result<Value> {
+    let path = get_required_arg("path", args, "file")?;
+
+    fs::read_to_string(base.join(&path))
+        .map(Value::from)
+        .map_err(|error| Error::msg(format!("Failed to read file `{}`: {}", path, error)))
+}
+
+/// `sha256(path="Cargo.lock")`
+fn sha256_function(args: &Args, base: &Path) -> Result<Value> {
+    let path = get_required_arg("path", args, "sha256")?;
+
+    fs::read(base.join(&path))
+        .map(|content| Value::from(sha256::digest(content.as_slice())))
+        .map_err(|error| Error::msg(format!("Failed to read file `{}`: {}", path, error)))
+}
+
+/// `glob(pattern="src/**/*.rs")`, paths matched by relative pattern are relative too
+fn glob_function(args: &Args, base: &Path) -> Result<Value> {
+    let pattern = get_required_arg("pattern", args, "glob")?;
+    let full_pattern = base.join(&pattern);
+
+    let paths = glob::glob(&full_pattern.display().to_string())
+        .map_err(|error| Error::msg(format!("Invalid glob pattern `{}`: {}", pattern, error)))?
+        .filter_map(|path| path.ok())
+        .map(|path| match path.strip_prefix(base) {
+            Ok(relative) if Path::new(&pattern).is_relative() => relative.display().to_string(),
+            _ => path.display().to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Value::from(paths))
+}
+
+/// `exists(path="Dockerfile")`
+fn exists_function(args: &Args, base: &Path) -> Result<Value> {
+    let path = get_required_arg("path", args, "exists")?;
+    Ok(Value::from(base.join(path).exists()))
+}
+
+/// `now(format="%Y-%m-%d", utc=false)`, RFC 3339 if format is omitted
+fn now_function(args: &Args) -> Result<Value> {
+    let format = get_string_arg("format", args, "now")?;
+    let utc = args.get("utc").and_then(|value| value.as_bool()) == Some(true);
+
+    // Keep compatibility with Tera built-in `now(timestamp=true)`
+    if args.get("timestamp").and_then(|value| value.as_bool()) == Some(true) {
+        return Ok(Value::from(chrono::Utc::now().timestamp()));
+    }
+
+    let value = match (format, utc) {
+        (Some(format), true) => chrono::Utc::now().format(&format).to_string(),
+        (Some(format), false) => chrono::Local::now().format(&format).to_string(),
+        (None, true) => chrono::Utc::now().to_rfc3339(),
+        (None, false) => chrono::Local::now().to_rfc3339(),
+    };
+    Ok(Value::from(value))
+}
+
+/// `value | shell_quote`, items of list are quoted and joined by space
+fn shell_quote_filter(value: &Value, _: &Args) -> Result<Value> {
+    let quote = |value: &Value| match value {
+        Value::String(value) => shell_quote(value),
+        value => shell_quote(&value.to_string()),
+    };
+    let value = match value {
+        Value::Array(items) => items.iter().map(quote).collect::<Vec<_>>().join(" "),
+        value => quote(value),
+    };
+    Ok(Value::from(value))
+}
+
+/// `"src" | path_join(path="main.rs")`
+fn path_join_filter(value: &Value, args: &Args) -> Result<Value> {
+    let base = get_string_value(value, "path_join")?;
+    let path = get_required_arg("path", args, "path_join")?;
+
+    Ok(Value::from(
+        PathBuf::from(base).join(path).display().to_string(),
+    ))
+}
+
+/// `path | relpath(base="/home")`, base is current directory by default
+fn relpath_filter(value: &Value, args: &Args) -> Result<Value> {
+    let path = PathBuf::from(get_string_value(value, "relpath")?);
+    let base = match get_string_arg("base", args, "relpath")? {
+        Some(base) => PathBuf::from(base),
+        None => env::current_dir().map_err(|error| Error::msg(error.to_string()))?,
+    };
+
+    Ok(Value::from(
+        relative_path(&path, &base).display().to_string(),
+    ))
+}
+
+/// Get path relative to base without accessing file system
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect();
+
+    let common = path_components
+        .iter()
+        .zip(base_components.iter())
+        .take_while(|(left, right)| left == right)
+        .count();
+
+    let mut result: PathBuf = base_components[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    result.extend(&path_components[common..]);
+
+    if result.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        result
+    }
+}
+
+/// `"1.2.3" | semver_bump(part="minor")`, `patch` is bumped by default
+fn semver_bump_filter(value: &Value, args: &Args) -> Result<Value> {
+    let value = get_string_value(value, "semver_bump")?;
+    let (prefix, version) = match value.strip_prefix('v') {
+        Some(version) => ("v", version),
+        None => ("", value.as_str()),
+    };
+
+    let mut version = semver::Version::parse(version)
+        .map_err(|error| Error::msg(format!("Invalid version `{}`: {}", value, error)))?;
+
+    match get_string_arg("part", args, "semver_bump")?.as_deref() {
+        Some("major") => {
+            version.major += 1;
+            version.minor = 0;
+            version.patch = 0;
+        }
+        Some("minor") => {
+            version.minor += 1;
+            version.patch = 0;
+        }
+        Some("patch") | None => version.patch += 1,
+        Some(part) => {
+            return Err(Error::msg(format!(
+                "Unknown version part `{}`, expected major, minor or patch",
+                part
+            )))
+        }
+    }
+    version.pre = semver::Prerelease::EMPTY;
+    version.build = semver::BuildMetadata::EMPTY;
+
+    Ok(Value::from(format!("{}{}", prefix, version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use tera::Context;
+
+    use super::*;
+
+    fn render(template: &str) -> Result<String> {
+        let mut tera = Tera::default();
+        register(&mut tera, &BaseDirectory::default());
+        tera.render_str(template, &Context::new())
+    }
+
+    #[test]
+    fn test_functions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("VERSION");
+        fs::write(&path, "1.2.3").unwrap();
+        let path = path.display();
+
+        assert_eq!(
+            render(&format!(r#"{{{{ file(path="{}") }}}}"#, path)).unwrap(),
+            "1.2.3"
+        );
+        assert_eq!(
+            render(&format!(r#"{{{{ sha256(path="{}") }}}}"#, path)).unwrap(),
+            sha256::digest("1.2.3")
+        );
+        assert_eq!(
+            render(&format!(
+                r#"{{{{ exists(path="{}") }}}} {{{{ glob(pattern="{}/*") | length }}}}"#,
+                path,
+                dir.path().display()
+            ))
+            .unwrap(),
+            "true 1"
+        );
+        assert_eq!(
+            render(r#"{{ env(name="DEVRC_TEST_MISSING", default="none") }}"#).unwrap(),
+            "none"
+        );
+        assert!(render(r#"{{ env(name="DEVRC_TEST_MISSING") }}"#).is_err());
+        assert_eq!(
+            render(r#"{{ os() }}-{{ arch() }}"#).unwrap(),
+            format!("{}-{}", env::consts::OS, env::consts::ARCH)
+        );
+        assert_eq!(render(r#"{{ uuid() | length }}"#).unwrap(), "36");
+        assert_eq!(render(r#"{{ now(format="%Y") | length }}"#).unwrap(), "4");
+        assert!(render(r#"{{ now(timestamp=true) }}"#)
+            .unwrap()
+            .parse::<i64>()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_functions_relative_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src").join("main.rs"), "fn main() {}").unwrap();
+
+        let directory = BaseDirectory::new(RwLock::new(Some(dir.path().to_path_buf())));
+        let mut tera = Tera::default();
+        register(&mut tera, &directory);
+
+        assert_eq!(
+            tera.render_str(
+                r#"{{ file(path="src/main.rs") }} {{ exists(path="src") }} {{ glob(pattern="src/*.rs") | join(sep=",") }}"#,
+                &Context::new()
+            )
+            .unwrap(),
+            "fn main() {} true src/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(
+            render(r#"{{ "my file" | shell_quote }}"#).unwrap(),
+            "'my file'"
+        );
+        assert_eq!(
+            render(r#"{{ ["main.rs", "my file.rs"] | shell_quote }}"#).unwrap(),
+            "main.rs 'my file.rs'"
+        );
+        assert_eq!(
+            render(r#"{{ "src" | path_join(path="main.rs") }}"#).unwrap(),
+            "src/main.rs"
+        );
+        assert_eq!(
+            render(r#"{{ "/a/b/c" | relpath(base="/a/d") }}"#).unwrap(),
+            "../b/c"
+        );
+        assert_eq!(
+            render(r#"{{ "v1.2.3-rc.1" | semver_bump(part="minor") }}"#).unwrap(),
+            "v1.3.0"
+        );
+        assert_eq!(render(r#"{{ "1.2.3" | semver_bump }}"#).unwrap(), "1.2.4");
+        assert!(render(r#"{{ "1.2" | semver_bump }}"#).is_err());
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("/a/b"), Path::new("/a/b")),
+            PathBuf::from(".")
+        );
+        assert_eq!(
+            relative_path(Path::new("src/main.rs"), Path::new(".")),
+            PathBuf::from("src/main.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a"), Path::new("/a/b/c")),
+            PathBuf::from("../..")
+        );
+    }
+}
@@ -1,46 +1,423 @@
 use core::fmt::Debug;
-use std::convert::TryFrom;
+use std::{
+    cell::{OnceCell, RefCell},
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use crate::{
+    auth::Auth,
+    check::get_used_names,
+    config::Config,
+    de::{deserialize_option_scalar, deserialize_some},
+    env_file::LocalFileImport,
     errors::{DevrcError, DevrcResult},
+    fetch::fetch_url,
+    interpreter::InterpreterKind,
+    loader::LoadingConfig,
+    origin::Origin,
+    resolver::{Location, PathResolve},
     scope::Scope,
     template::render_string,
+    utils::shell_quote,
     variables_parser::parse_key,
 };
 
+use devrc_plugins::execution::ExecutionPluginManager;
+use reqwest::StatusCode;
 use serde::Deserialize;
+use url::Url;
 
-#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Http {
     fetch: String,
+
+    #[serde(default)]
+    headers: indexmap::IndexMap<String, String>,
+
+    #[serde(default)]
+    auth: crate::raw::auth::Auth,
+
+    #[serde(default, deserialize_with = "deserialize_some")]
+    checksum: Option<String>,
+
+    /// Path of value in JSON response, e.g. `.assets[0].name`
+    #[serde(default)]
+    jq: Option<String>,
+
+    /// Remove leading and trailing whitespaces
+    #[serde(default = "get_default_trim")]
+    trim: bool,
+
+    #[serde(default)]
+    format: ValueFormat,
+
+    #[serde(skip)]
+    loading_config: LoadingConfig,
+}
+
+impl Http {
+    /// Load variable value, it's loaded again when variables
+    /// used in url or headers are changed
+    pub fn evaluate(&self, name: &str, scope: &Scope) -> DevrcResult<VariableValue> {
+        let mut templates = vec![self.fetch.as_str()];
+        templates.extend(self.headers.values().map(|value| value.as_str()));
+
+        let mut value = self.load(name, scope)?;
+        value.dependencies = get_dependencies(name, &templates)?;
+        value.source = Some(ValueSource::Http(self.clone()));
+        Ok(value)
+    }
+
+    fn load(&self, name: &str, scope: &Scope) -> DevrcResult<VariableValue> {
+        let fetch = render_string(name, &self.fetch, scope)?;
+        let url = Url::parse(&fetch).map_err(|_| DevrcError::InvalidIncludeUrl(fetch.clone()))?;
+
+        let content = self.get_content(name, &url, scope)?;
+        let content = if self.trim {
+            content.trim().to_string()
+        } else {
+            content
+        };
+
+        let format = match (self.format, &self.jq) {
+            (ValueFormat::Raw, Some(_)) => ValueFormat::Json,
+            (format, _) => format,
+        };
+
+        let structured =
+            match (format.parse(name, &content)?, &self.jq) {
+                (Some(value), Some(path)) => Some(select_path(&value, path).ok_or_else(|| {
+                    DevrcError::VariableParseError {
+                        name: name.to_string(),
+                        reason: format!("path `{}` not found", path),
+                    }
+                })?),
+                (value, _) => value,
+            };
+
+        Ok(VariableValue::from_content(name, &content, structured))
+    }
+
+    fn get_content(&self, name: &str, url: &Url, scope: &Scope) -> DevrcResult<String> {
+        let config = &self.loading_config;
+
+        let auth = match &self.auth {
+            crate::raw::auth::Auth::Empty => Auth::Empty,
+            raw_auth => Auth::try_from(raw_auth.clone())?,
+        };
+
+        let mut headers = Vec::new();
+        for (key, value) in &self.headers {
+            headers.push((key.clone(), render_string(name, value, scope)?));
+        }
+        headers.extend(auth.get_header());
+
+        if let Some(cache_ttl) = config.cache_ttl {
+            if let Some(content) =
+                crate::cache::load(url, &headers, config, self.checksum.as_deref(), &cache_ttl)
+            {
+                config.log_level.debug(
+                    &format!("\n==> Loading VARIABLE URL CACHE: `{}` ...", &url),
+                    &config.designer.banner(),
+                );
+                return Ok(content);
+            }
+        }
+
+        config.log_level.debug(
+            &format!("\n==> Loading VARIABLE URL: `{}` ...", &url),
+            &config.designer.banner(),
+        );
+
+        let content = fetch_url(url, &headers, self.checksum.as_deref())?;
+
+        if config.cache_ttl.is_some() {
+            crate::cache::save(url, &headers, &content)?;
+        }
+
+        Ok(content)
+    }
 }
 
-#[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
+/// Select value by jq-like path, e.g. `.items[0].name`
+pub fn select_path(value: &tera::Value, path: &str) -> Option<tera::Value> {
+    let mut current = value;
+
+    for part in path.split('.').filter(|part| !part.is_empty()) {
+        let (key, indexes) = match part.find('[') {
+            Some(position) => part.split_at(position),
+            None => (part, ""),
+        };
+
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+
+        for index in indexes.split_terminator(']') {
+            let index = index.strip_prefix('[')?;
+            current = match index.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+                Some(key) => current.get(key)?,
+                None => current.get(index.parse::<usize>().ok()?)?,
+            };
+        }
+    }
+
+    Some(current.clone())
+}
+
+/// Format of loaded variable value
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueFormat {
+    #[default]
+    Raw,
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ValueFormat {
+    /// Detect format of structured file by extension of its path or url
+    pub fn from_extension(name: &str) -> Option<Self> {
+        let path = name.split(['?', '#']).next().unwrap_or_default();
+        match Path::new(path).extension().and_then(|value| value.to_str()) {
+            Some("json") => Some(ValueFormat::Json),
+            Some("yaml") | Some("yml") => Some(ValueFormat::Yaml),
+            Some("toml") => Some(ValueFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Parse content to structured value, raw content isn't parsed
+    pub fn parse(&self, name: &str, content: &str) -> DevrcResult<Option<tera::Value>> {
+        let error = |reason: String| DevrcError::VariableParseError {
+            name: name.to_string(),
+            reason,
+        };
+
+        let value = match self {
+            ValueFormat::Raw => return Ok(None),
+            ValueFormat::Json => {
+                serde_json::from_str(content).map_err(|err| error(err.to_string()))?
+            }
+            ValueFormat::Yaml => {
+                serde_yaml::from_str(content).map_err(|err| error(err.to_string()))?
+            }
+            ValueFormat::Toml => toml::from_str(content).map_err(|err| error(err.to_string()))?,
+        };
+        Ok(Some(value))
+    }
+}
+
+fn get_default_trim() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct File {
     file: String,
+
+    /// Remove leading and trailing whitespaces
+    #[serde(default = "get_default_trim")]
+    trim: bool,
+
+    #[serde(default)]
+    format: ValueFormat,
+
+    #[serde(default)]
+    path_resolve: PathResolve,
+
+    /// Value which is used if file doesn't exist
+    #[serde(default, deserialize_with = "deserialize_option_scalar")]
+    default: Option<String>,
+
+    /// Location of Devrcfile where variable is defined
+    #[serde(skip)]
+    location: Location,
+
+    #[serde(skip)]
+    loading_config: LoadingConfig,
+}
+
+impl File {
+    /// Load variable value, it's loaded again when variables
+    /// used in file path are changed
+    pub fn evaluate(&self, name: &str, scope: &Scope) -> DevrcResult<VariableValue> {
+        let mut value = self.load(name, scope)?;
+        value.dependencies = get_dependencies(name, &[&self.file])?;
+        value.source = Some(ValueSource::File(self.clone()));
+        Ok(value)
+    }
+
+    fn load(&self, name: &str, scope: &Scope) -> DevrcResult<VariableValue> {
+        let import = LocalFileImport {
+            file: PathBuf::from(render_string(name, &self.file, scope)?),
+            path_resolve: self.path_resolve.clone(),
+            ..Default::default()
+        };
+        let config = self.loading_config.clone();
+
+        // Resolving of local file path fails if file doesn't exist
+        let result = import
+            .get_loading_location(self.location.clone(), &config)
+            .and_then(|_| import.get_content(self.location.clone(), config));
+
+        let content = match (result, &self.default) {
+            (Ok(content), _) => content,
+            (Err(DevrcError::FileNotExists(_)), Some(default)) => {
+                return Ok(VariableValue::from_content(name, default, None))
+            }
+            (
+                Err(DevrcError::EnvfileUrlImportStatusError {
+                    status: StatusCode::NOT_FOUND,
+                    ..
+                }),
+                Some(default),
+            ) => return Ok(VariableValue::from_content(name, default, None)),
+            (Err(error), _) => return Err(error),
+        };
+
+        let content = if self.trim {
+            content.trim().to_string()
+        } else {
+            content
+        };
+
+        let structured = self.format.parse(name, &content)?;
+
+        Ok(VariableValue::from_content(name, &content, structured))
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Computable {
-    #[allow(dead_code)]
     exec: String,
+
+    /// Interpreter which is used instead of configured one
+    #[serde(default, alias = "shell")]
+    interpreter: Option<InterpreterKind>,
+
+    /// Options of Devrcfile or task where variable is defined
+    #[serde(skip)]
+    config: Config,
+
+    #[serde(skip)]
+    registry: Rc<RefCell<ExecutionPluginManager>>,
 }
 
+impl Computable {
+    /// Create variable which value is computed on first use
+    pub fn evaluate(&self, name: &str, scope: &Scope) -> DevrcResult<VariableValue> {
+        let computed = ComputedValue {
+            exec: self.exec.clone(),
+            interpreter: self
+                .interpreter
+                .clone()
+                .unwrap_or_else(|| self.config.interpreter.clone()),
+            config: self.config.clone(),
+            registry: Rc::clone(&self.registry),
+            scope: Rc::new(scope.compute_execution_scope()?),
+            result: Rc::new(OnceCell::new()),
+        };
+
+        Ok(VariableValue {
+            name: name.to_owned(),
+            original: self.exec.clone(),
+            rendered: None,
+            raw: false,
+            structured: None,
+            computed: Some(computed),
+            origin: Origin::None,
+            previous: None,
+            dependencies: get_dependencies(name, &[&self.exec])?,
+            source: None,
+        })
+    }
+}
+
+/// Command which output becomes variable value
+#[derive(Debug, Clone)]
+pub struct ComputedValue {
+    pub exec: String,
+    pub interpreter: InterpreterKind,
+    config: Config,
+    registry: Rc<RefCell<ExecutionPluginManager>>,
+
+    /// Variables and environment available at definition
+    scope: Rc<Scope>,
+
+    /// Output is shared between copies of variable to compute it once
+    result: Rc<OnceCell<String>>,
+}
+
+impl ComputedValue {
+    pub fn get_result(&self) -> Option<&String> {
+        self.result.get()
+    }
+
+    pub fn compute(&self, name: &str) -> DevrcResult<String> {
+        if let Some(value) = self.result.get() {
+            return Ok(value.clone());
+        }
+
+        let wrap_error = |error| DevrcError::ComputableVariableError {
+            name: name.to_string(),
+            inner: Box::new(error),
+        };
+
+        let code = render_string(name, &self.exec, &self.scope).map_err(wrap_error)?;
+
+        // Command isn't executed in dry run, it's shown instead of value
+        if self.config.dry_run {
+            return Ok(self.result.get_or_init(|| format!("$({})", code)).clone());
+        }
+
+        let output = self
+            .interpreter
+            .output(&code, &self.scope, &self.config, Rc::clone(&self.registry))
+            .map_err(wrap_error)?;
+
+        Ok(self
+            .result
+            .get_or_init(|| output.trim().to_string())
+            .clone())
+    }
+}
+
+impl PartialEq for ComputedValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.exec == other.exec && Rc::ptr_eq(&self.result, &other.result)
+    }
+}
+
+impl Eq for ComputedValue {}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 #[serde(untagged)]
 pub enum ValueKind {
     #[default]
     None,
     String(String),
+    // Sequences must be checked before structs which can be parsed from them
+    List(Vec<serde_yaml::Value>),
     Http(Http),
     File(File),
     Computable(Computable),
+    Map(indexmap::IndexMap<String, serde_yaml::Value>),
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct RawVariables {
     #[serde(flatten)]
     pub vars: indexmap::IndexMap<String, ValueKind>,
+
+    #[serde(skip)]
+    pub origin: Origin,
 }
 
 pub type Variables = indexmap::IndexMap<VariableKey, VariableValue>;
@@ -54,6 +431,7 @@ pub struct VariableKey {
     pub name: String,
     pub set_global: bool,
     pub raw: bool,
+    pub secret: bool,
 }
 
 // impl From<String> for VariableKey {
@@ -76,14 +454,62 @@ impl VariableKey {
     }
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, Default, Clone)]
 pub struct VariableValue {
     pub name: String,
     pub original: String,
     pub rendered: Option<String>,
     pub raw: bool,
+
+    /// Value which is passed to template engine as is instead of rendered string
+    pub structured: Option<tera::Value>,
+
+    /// Value is computed from command output on first use
+    pub computed: Option<ComputedValue>,
+
+    /// Where variable is defined
+    pub origin: Origin,
+
+    /// Overridden value with the same name
+    pub previous: Option<Box<VariableValue>>,
+
+    /// Names of other variables which value is rendered from
+    pub dependencies: Vec<String>,
+
+    /// File or url which value is loaded from
+    pub source: Option<ValueSource>,
 }
 
+/// Definition of loaded value which is used to load it again
+#[derive(Debug, Clone)]
+pub enum ValueSource {
+    File(File),
+    Http(Http),
+}
+
+impl ValueSource {
+    pub fn evaluate(&self, name: &str, scope: &Scope) -> DevrcResult<VariableValue> {
+        match self {
+            Self::File(file) => file.evaluate(name, scope),
+            Self::Http(http) => http.evaluate(name, scope),
+        }
+    }
+}
+
+// Origin and overridden values don't affect the value itself
+impl PartialEq for VariableValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.original == other.original
+            && self.rendered == other.rendered
+            && self.raw == other.raw
+            && self.structured == other.structured
+            && self.computed == other.computed
+    }
+}
+
+impl Eq for VariableValue {}
+
 impl VariableValue {
     pub fn new(name: &str, raw: &str) -> Self {
         Self {
@@ -91,16 +517,184 @@ impl VariableValue {
             original: raw.to_owned(),
             rendered: None,
             raw: false,
+            structured: None,
+            computed: None,
+            origin: Origin::None,
+            previous: None,
+            dependencies: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Create variable from list of values, items are quoted for shell
+    /// only when list is shown as a whole
+    pub fn from_list(name: &str, values: &[String]) -> Self {
+        let joined = values
+            .iter()
+            .map(|value| shell_quote(value))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            name: name.to_owned(),
+            original: joined.clone(),
+            rendered: Some(joined),
+            raw: false,
+            structured: Some(tera::Value::from(values.to_vec())),
+            computed: None,
+            origin: Origin::None,
+            previous: None,
+            dependencies: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Create variable from loaded content which isn't a template
+    pub fn from_content(name: &str, content: &str, structured: Option<tera::Value>) -> Self {
+        let rendered = match &structured {
+            None | Some(tera::Value::String(_)) => content.to_owned(),
+            Some(value) => value.to_string(),
+        };
+
+        Self {
+            name: name.to_owned(),
+            original: content.to_owned(),
+            rendered: Some(rendered),
+            raw: false,
+            structured,
+            computed: None,
+            origin: Origin::None,
+            previous: None,
+            dependencies: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Create variable from list or map, string items are templates
+    pub fn from_structured(
+        name: &str,
+        value: tera::Value,
+        raw: bool,
+        scope: &Scope,
+    ) -> DevrcResult<Self> {
+        let original = value.to_string();
+        let (structured, dependencies) = if raw {
+            (value, Vec::new())
+        } else {
+            let mut templates = Vec::new();
+            collect_templates(&value, &mut templates);
+            let dependencies = get_dependencies(name, &templates)?;
+            (render_structured(name, value, scope)?, dependencies)
+        };
+        let rendered = structured.to_string();
+
+        Ok(Self {
+            name: name.to_owned(),
+            original,
+            rendered: Some(rendered),
+            raw: false,
+            structured: Some(structured),
+            computed: None,
+            origin: Origin::None,
+            previous: None,
+            dependencies,
+            source: None,
+        })
+    }
+
+    /// Get value which is inserted into template context
+    pub fn get_context_value(&self) -> tera::Value {
+        match &self.structured {
+            Some(value) => value.clone(),
+            None => tera::Value::String(self.get_rendered_value()),
         }
     }
 
     pub fn get_rendered_value(&self) -> String {
+        if let Some(value) = self.computed.as_ref().and_then(|value| value.get_result()) {
+            return value.clone();
+        }
         if self.raw {
             return self.original.clone();
         }
         self.rendered.clone().unwrap_or_default()
     }
 
+    /// Get value, computable value is computed if it wasn't done before
+    pub fn get_value(&self) -> DevrcResult<String> {
+        match &self.computed {
+            Some(computed) => computed.compute(&self.name),
+            None => Ok(self.get_rendered_value()),
+        }
+    }
+
+    /// Get value for listing, lists and maps are pretty printed
+    pub fn get_display_value(&self) -> DevrcResult<String> {
+        match &self.structured {
+            Some(value @ (tera::Value::Array(_) | tera::Value::Object(_))) => {
+                serde_json::to_string_pretty(value).map_err(|error| {
+                    DevrcError::VariableParseError {
+                        name: self.name.clone(),
+                        reason: error.to_string(),
+                    }
+                })
+            }
+            _ => Ok(format!("\"{}\"", self.get_value()?)),
+        }
+    }
+
+    /// Get this value and overridden values starting from the first one
+    pub fn get_overrides_chain(&self) -> Vec<&VariableValue> {
+        let mut chain = vec![self];
+        while let Some(previous) = chain.last().and_then(|value| value.previous.as_deref()) {
+            chain.push(previous);
+        }
+        chain.reverse();
+        chain
+    }
+
+    pub fn with_origin(mut self, origin: Origin, previous: Option<VariableValue>) -> Self {
+        self.origin = origin;
+        self.previous = previous.map(Box::new);
+        self
+    }
+
+    /// Computable value which isn't used yet
+    pub fn is_lazy(&self) -> bool {
+        matches!(&self.computed, Some(computed) if computed.get_result().is_none())
+    }
+
+    /// Render value again after variables it depends on are changed
+    pub fn rerender(&mut self, scope: &Scope) -> DevrcResult<()> {
+        if let Some(source) = &self.source {
+            let value = source.evaluate(&self.name, scope)?;
+            self.original = value.original;
+            self.rendered = value.rendered;
+            self.structured = value.structured;
+            return Ok(());
+        }
+
+        if let Some(computed) = &mut self.computed {
+            computed.scope = Rc::new(scope.compute_execution_scope()?);
+            computed.result = Rc::new(OnceCell::new());
+            return Ok(());
+        }
+
+        if self.structured.is_some() {
+            let template = serde_json::from_str(&self.original).map_err(|error| {
+                DevrcError::VariableParseError {
+                    name: self.name.clone(),
+                    reason: error.to_string(),
+                }
+            })?;
+            let structured = render_structured(&self.name, template, scope)?;
+            self.rendered = Some(structured.to_string());
+            self.structured = Some(structured);
+            return Ok(());
+        }
+
+        self.render_value(&self.name.clone(), scope)
+    }
+
     pub fn render_value(&mut self, name: &str, scope: &Scope) -> DevrcResult<()> {
         self.rendered = Some(render_string(name, &self.original, scope)?);
         Ok(())
@@ -108,6 +702,7 @@ impl VariableValue {
 
     pub fn with_render_value(mut self, scope: &Scope) -> DevrcResult<Self> {
         self.rendered = Some(render_string(&self.name, &self.original, scope)?);
+        self.dependencies = get_dependencies(&self.name, &[&self.original])?;
         Ok(self)
     }
 
@@ -121,12 +716,65 @@ impl RawVariables {
     pub fn add(&mut self, name: &str, value: ValueKind) {
         self.vars.insert(name.to_owned(), value);
     }
+
+    /// Set location of Devrcfile where variables are defined and loading options
+    pub fn setup_loading(&mut self, location: &Location, config: &LoadingConfig) {
+        for value in self.vars.values_mut() {
+            match value {
+                ValueKind::File(file) => {
+                    file.location = location.clone();
+                    file.loading_config = config.clone();
+                }
+                ValueKind::Http(http) => http.loading_config = config.clone(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Get names of variables which templates of every variable refer to,
+    /// raw variables aren't rendered and don't have dependencies
+    pub fn get_dependencies(&self) -> DevrcResult<indexmap::IndexMap<String, Vec<String>>> {
+        let mut result: indexmap::IndexMap<String, Vec<String>> = indexmap::IndexMap::new();
+        for (original_key, value) in &self.vars {
+            let key = VariableKey::try_from(original_key.clone())?;
+            let dependencies = result.entry(key.name.clone()).or_default();
+            if key.raw {
+                continue;
+            }
+
+            let templates = value.get_templates();
+            let templates: Vec<&str> = templates.iter().map(|value| value.as_str()).collect();
+            for name in get_dependencies(&key.name, &templates)? {
+                if !dependencies.contains(&name) {
+                    dependencies.push(name);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Set options and plugins which are used by computable variables
+    pub fn setup_execution(
+        &mut self,
+        config: &Config,
+        registry: &Rc<RefCell<ExecutionPluginManager>>,
+    ) {
+        for value in self.vars.values_mut() {
+            if let ValueKind::Computable(computable) = value {
+                computable.config = config.clone();
+                computable.registry = Rc::clone(registry);
+            }
+        }
+    }
 }
 
 impl Default for RawVariables {
     fn default() -> Self {
         let vars = indexmap::IndexMap::new();
-        Self { vars }
+        Self {
+            vars,
+            origin: Origin::None,
+        }
     }
 }
 
@@ -137,38 +785,468 @@ impl From<Vec<(String, String)>> for RawVariables {
                 .iter()
                 .map(move |x| (x.0.clone(), ValueKind::String(x.1.clone())))
                 .collect::<indexmap::IndexMap<String, ValueKind>>(),
+            origin: Origin::None,
         }
     }
 }
 
+/// Get names of other variables which templates refer to
+fn get_dependencies(name: &str, templates: &[&str]) -> DevrcResult<Vec<String>> {
+    let mut dependencies: Vec<String> = Vec::new();
+    for template in templates {
+        for used in get_used_names(name, template)? {
+            if used != name && !dependencies.contains(&used) {
+                dependencies.push(used);
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+/// Collect string items of list or map which are templates
+fn collect_templates<'a>(value: &'a tera::Value, result: &mut Vec<&'a str>) {
+    match value {
+        tera::Value::String(value) => result.push(value),
+        tera::Value::Array(items) => {
+            for item in items {
+                collect_templates(item, result);
+            }
+        }
+        tera::Value::Object(items) => {
+            for item in items.values() {
+                collect_templates(item, result);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Render string items of list or map
+fn render_structured(name: &str, value: tera::Value, scope: &Scope) -> DevrcResult<tera::Value> {
+    Ok(match value {
+        tera::Value::String(template) => {
+            tera::Value::String(render_string(name, &template, scope)?)
+        }
+        tera::Value::Array(items) => tera::Value::Array(
+            items
+                .into_iter()
+                .map(|item| render_structured(name, item, scope))
+                .collect::<DevrcResult<_>>()?,
+        ),
+        tera::Value::Object(items) => tera::Value::Object(
+            items
+                .into_iter()
+                .map(|(key, item)| Ok((key, render_structured(name, item, scope)?)))
+                .collect::<DevrcResult<_>>()?,
+        ),
+        value => value,
+    })
+}
+
+/// Collect string items of list or map
+fn collect_strings(value: &serde_yaml::Value, result: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::String(value) => result.push(value.clone()),
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                collect_strings(item, result);
+            }
+        }
+        serde_yaml::Value::Mapping(items) => {
+            for item in items.values() {
+                collect_strings(item, result);
+            }
+        }
+        _ => {}
+    }
+}
+
 impl ValueKind {
+    /// Get templates which are rendered to compute value
+    pub fn get_templates(&self) -> Vec<String> {
+        let mut result = Vec::new();
+
+        match self {
+            Self::None => {}
+            Self::String(template) => result.push(template.clone()),
+            Self::Http(http) => {
+                result.push(http.fetch.clone());
+                result.extend(http.headers.values().cloned());
+            }
+            Self::File(file) => result.push(file.file.clone()),
+            Self::Computable(computable) => result.push(computable.exec.clone()),
+            Self::List(items) => {
+                for item in items {
+                    collect_strings(item, &mut result);
+                }
+            }
+            Self::Map(items) => {
+                for item in items.values() {
+                    collect_strings(item, &mut result);
+                }
+            }
+        }
+        result
+    }
+
+    /// Convert list or map into template engine value
+    pub fn get_structured(&self, name: &str) -> DevrcResult<Option<tera::Value>> {
+        let value = match self {
+            Self::List(items) => serde_json::to_value(items),
+            Self::Map(items) => serde_json::to_value(items),
+            _ => return Ok(None),
+        };
+
+        value
+            .map(Some)
+            .map_err(|error| DevrcError::VariableParseError {
+                name: name.to_string(),
+                reason: error.to_string(),
+            })
+    }
+
     pub fn evaluate(&self, name: &str, scope: &Scope) -> DevrcResult<String> {
         match self {
             Self::String(template) => render_string(name, template, scope),
             Self::None => Err(DevrcError::EmptyVariable),
-            Self::Http(_) => Ok("TODO: replace me".to_owned()),
-            Self::File(_) => Ok("TODO: replace me".to_owned()),
-            Self::Computable(_) => Ok("TODO: replace me".to_owned()),
+            Self::Http(http) => Ok(http.evaluate(name, scope)?.get_rendered_value()),
+            Self::File(file) => Ok(file.evaluate(name, scope)?.get_rendered_value()),
+            Self::Computable(computable) => computable.evaluate(name, scope)?.get_value(),
+            Self::List(_) | Self::Map(_) => Ok(VariableValue::from_structured(
+                name,
+                self.get_structured(name)?.unwrap_or_default(),
+                false,
+                scope,
+            )?
+            .get_rendered_value()),
         }
     }
 }
 
-// #[cfg(test)]
-// mod tests {
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
 
-//     use super::*;
+    use sha256::digest;
 
-//     use crate::errors::DevrcError;
-//     use std::error::Error as StdError;
+    use super::*;
 
-//     use tera::Error as TeraError;
+    fn load_variables(content: &str, location: &Location) -> Scope {
+        let mut variables: RawVariables = serde_yaml::from_str(content).unwrap();
+        variables.setup_loading(location, &LoadingConfig::default());
 
-//     #[test]
-//     fn test_file_variable() {}
+        let mut scope = Scope::default();
+        scope.process_raw_vars(&variables).unwrap();
+        scope
+    }
 
-//     #[test]
-//     fn test_http_variable() {}
+    #[test]
+    fn test_file_variable() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Devrcfile"), "").unwrap();
+        fs::write(dir.path().join("VERSION"), "1.2.3\n").unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"name": "app"}"#).unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nversion = \"0.6.0\"\n",
+        )
+        .unwrap();
 
-//     #[test]
-//     fn test_computable_variable() {}
-// }
+        let location = Location::LocalFile(dir.path().join("Devrcfile"));
+        let scope = load_variables(
+            r#"
+version:
+  file: VERSION
+untrimmed:
+  file: VERSION
+  trim: false
+package:
+  file: package.json
+  format: json
+manifest:
+  file: Cargo.toml
+  format: toml
+missing:
+  file: MISSING
+  default: "0.0.0"
+"#,
+            &location,
+        );
+
+        let get = |name: &str| {
+            scope
+                .get_var(&VariableKey::try_from(name.to_string()).unwrap())
+                .unwrap()
+                .clone()
+        };
+
+        assert_eq!(get("version").get_rendered_value(), "1.2.3");
+        assert_eq!(get("untrimmed").get_rendered_value(), "1.2.3\n");
+        assert_eq!(get("missing").get_rendered_value(), "0.0.0");
+        assert_eq!(
+            render_string(
+                "test",
+                "{{ package.name }} {{ manifest.package.version }}",
+                &scope
+            )
+            .unwrap(),
+            "app 0.6.0"
+        );
+
+        let mut variables = RawVariables::default();
+        variables.add("missing", serde_yaml::from_str("{file: MISSING}").unwrap());
+        variables.setup_loading(&location, &LoadingConfig::default());
+
+        match Scope::default().process_raw_vars(&variables) {
+            Err(DevrcError::FileNotExists(path)) => assert_eq!(path, dir.path().join("MISSING")),
+            _ => unreachable!(),
+        }
+
+        // File is loaded again when variable used in its path is overridden
+        fs::write(dir.path().join("dev.json"), r#"{"host": "localhost"}"#).unwrap();
+        fs::write(dir.path().join("prod.json"), r#"{"host": "example.com"}"#).unwrap();
+        let mut scope = load_variables(
+            r#"
+env: dev
+config:
+  file: "{{ env }}.json"
+  format: json
+"#,
+            &location,
+        );
+
+        let mut overrides = RawVariables::default();
+        overrides.add("env", ValueKind::String("prod".to_owned()));
+        let changed = scope.process_override_vars(&overrides).unwrap();
+
+        assert!(changed.contains("config"));
+        assert_eq!(
+            render_string("test", "{{ config.host }}", &scope).unwrap(),
+            "example.com"
+        );
+    }
+
+    /// Serve `body` for given number of requests which have `x-token: abc` header
+    fn serve(body: &'static str, requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut buffer = [0; 4096];
+                let size = stream.read(&mut buffer).unwrap();
+                let request = String::from_utf8_lossy(&buffer[..size]).to_lowercase();
+
+                let status = if request.contains("x-token: abc") {
+                    "200 OK"
+                } else {
+                    "401 Unauthorized"
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        format!("http://{}/releases/latest", address)
+    }
+
+    #[test]
+    fn test_http_variable() {
+        let body = r#"{"tag_name": "v1.2.3", "assets": [{"name": "devrc.tar.gz"}]}"#;
+        let url = serve(body, 4);
+
+        let content = format!(
+            r#"
+token: abc
+latest:
+  fetch: "{url}"
+  headers:
+    X-Token: "{{{{ token }}}}"
+  checksum: "{checksum}"
+  jq: .tag_name
+asset:
+  fetch: "{url}"
+  headers:
+    X-Token: abc
+  jq: .assets[0].name
+release:
+  fetch: "{url}"
+  headers:
+    X-Token: abc
+  format: json
+"#,
+            url = url,
+            checksum = digest(body)
+        );
+        let scope = load_variables(&content, &Location::None);
+
+        assert_eq!(
+            render_string(
+                "test",
+                "{{ latest }} {{ asset }} {{ release.tag_name }}",
+                &scope
+            )
+            .unwrap(),
+            "v1.2.3 devrc.tar.gz v1.2.3"
+        );
+
+        let mut variables = RawVariables::default();
+        variables.add(
+            "latest",
+            serde_yaml::from_str(&format!("{{fetch: \"{}\"}}", url)).unwrap(),
+        );
+
+        match Scope::default().process_raw_vars(&variables) {
+            Err(DevrcError::UrlImportStatusError { status, .. }) => {
+                assert_eq!(status, StatusCode::UNAUTHORIZED)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_select_path() {
+        let value: tera::Value =
+            serde_json::from_str(r#"{"a": {"b": [1, {"c": "d"}]}, "e-f": 2}"#).unwrap();
+
+        assert_eq!(select_path(&value, ".a.b[1].c"), Some("d".into()));
+        assert_eq!(select_path(&value, ".a.b[0]"), Some(1.into()));
+        assert_eq!(select_path(&value, r#".["e-f"]"#), Some(2.into()));
+        assert_eq!(select_path(&value, ".a.x"), None);
+        assert_eq!(select_path(&value, "."), Some(value.clone()));
+    }
+
+    #[test]
+    fn test_computable_variable() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("counter");
+
+        let location = Location::LocalFile(dir.path().join("Devrcfile"));
+        let scope = load_variables(
+            &format!(
+                r#"
+sha:
+  exec: echo run >> {0}; echo abc123
+unused:
+  exec: echo unused >> {0}
+name: world
+greeting:
+  exec: echo hello {{{{ name }}}}
+broken:
+  exec: exit 3
+"#,
+                counter.display()
+            ),
+            &location,
+        );
+
+        // Names mentioned in plain text are not computed
+        assert_eq!(
+            render_string("test", "sha of {{ name }}", &scope).unwrap(),
+            "sha of world"
+        );
+        assert!(!counter.exists());
+
+        assert_eq!(
+            render_string("test", "{{ sha }} {{ sha }}", &scope).unwrap(),
+            "abc123 abc123"
+        );
+        assert_eq!(
+            render_string("test", "{{ sha }} {{ greeting }}", &scope).unwrap(),
+            "abc123 hello world"
+        );
+        // Only referenced variables are computed, and only once
+        assert_eq!(fs::read_to_string(&counter).unwrap(), "run\n");
+
+        match render_string("test", "{{ broken }}", &scope) {
+            Err(DevrcError::ComputableVariableError { name, inner }) => {
+                assert_eq!(name, "broken");
+                assert!(matches!(*inner, DevrcError::Code { code: 3 }));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_computable_variable_dry_run() {
+        let mut variables: RawVariables =
+            serde_yaml::from_str("sha:\n  exec: git rev-parse {{ ref }}\nref: HEAD\n").unwrap();
+        let config = Config {
+            dry_run: true,
+            ..Default::default()
+        };
+        variables.setup_execution(
+            &config,
+            &Rc::new(RefCell::new(ExecutionPluginManager::new())),
+        );
+
+        let mut scope = Scope::default();
+        scope.process_raw_vars(&variables).unwrap();
+
+        assert_eq!(
+            render_string("test", "{{ sha }}", &scope).unwrap(),
+            "$(git rev-parse HEAD)"
+        );
+    }
+
+    #[test]
+    fn test_structured_variable() {
+        let location = Location::LocalFile(PathBuf::from("Devrcfile"));
+        let scope = load_variables(
+            r#"
+host: example.com
+services: [api, worker]
+ports:
+  api: 8080
+  worker: 9090
+urls:
+  - "https://{{ host }}/api"
+templates +raw: ["{{ host }}"]
+artifact:
+  file: app.tar.gz
+  exec: ./build.sh
+"#,
+            &location,
+        );
+
+        // Maps with keys which are not options of loaded values are kept as maps
+        assert_eq!(
+            render_string("test", "{{ artifact.file }} {{ artifact.exec }}", &scope).unwrap(),
+            "app.tar.gz ./build.sh"
+        );
+
+        assert_eq!(
+            render_string(
+                "test",
+                "{% for s in services %}{{ s }}:{{ ports[s] }} {% endfor %}{{ urls[0] }}",
+                &scope
+            )
+            .unwrap(),
+            "api:8080 worker:9090 https://example.com/api"
+        );
+        assert_eq!(
+            render_string("test", "{{ templates[0] }}", &scope).unwrap(),
+            "{{ host }}"
+        );
+
+        let services = scope
+            .get_var(&VariableKey::try_from("services".to_string()).unwrap())
+            .unwrap();
+        assert_eq!(
+            services.get_display_value().unwrap(),
+            "[\n  \"api\",\n  \"worker\"\n]"
+        );
+    }
+}
@@ -5,17 +5,26 @@ use crate::{
 
 const GLOBAL_MODIFIER: &str = "+global";
 const RAW_MODIFIER: &str = "+raw";
+const SECRET_MODIFIER: &str = "+secret";
+
+/// Names used by devrc in templates context
+const RESERVED_NAMES: [&str; 1] = ["devrc"];
 
 pub fn parse_key(value: &str) -> DevrcResult<VariableKey> {
-    let mut parts = value.split(|c| c == ' ' || c == '\t');
+    let mut parts = value.split([' ', '\t']);
 
     let name = parts.next().ok_or(DevrcError::InvalidVariableName)?;
 
+    if RESERVED_NAMES.contains(&name) {
+        return Err(DevrcError::ReservedVariableName(name.to_string()));
+    }
+
     let mut key = VariableKey {
         original: value.to_string(),
         name: name.to_string(),
         set_global: false,
         raw: false,
+        secret: false,
     };
 
     for part in parts {
@@ -23,6 +32,8 @@ pub fn parse_key(value: &str) -> DevrcResult<VariableKey> {
             key.set_global = true;
         } else if part == RAW_MODIFIER {
             key.raw = true;
+        } else if part == SECRET_MODIFIER {
+            key.secret = true;
         } else {
             return Err(DevrcError::InvalidVariableModifier);
         }
@@ -45,6 +56,7 @@ mod tests {
             name: "name".to_string(),
             set_global: true,
             raw: true,
+            secret: false,
         };
         assert_eq!(result, control);
     }
@@ -58,6 +70,21 @@ mod tests {
             name: "name".to_string(),
             set_global: false,
             raw: false,
+            secret: false,
+        };
+        assert_eq!(result, control);
+    }
+
+    #[test]
+    fn test_parse_variables_key_with_secret() {
+        let result = parse_key("token +secret").unwrap();
+
+        let control = VariableKey {
+            original: "token +secret".to_string(),
+            name: "token".to_string(),
+            set_global: false,
+            raw: false,
+            secret: true,
         };
         assert_eq!(result, control);
     }
@@ -71,4 +98,12 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_parse_reserved_variables_key() {
+        assert!(matches!(
+            parse_key("devrc +global"),
+            Err(DevrcError::ReservedVariableName(name)) if name == "devrc"
+        ));
+    }
 }
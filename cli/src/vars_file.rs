@@ -0,0 +1,209 @@
+use indexmap::{IndexMap, IndexSet};
+use serde::Deserialize;
+
+use crate::{
+    env_file::{EnvFilesInclude, LocalFileImport},
+    errors::{DevrcError, DevrcResult},
+    loader::LoadingConfig,
+    origin::Origin,
+    resolver::Location,
+    variables::ValueFormat,
+};
+
+/// Files with structured data which keys become variables
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(from = "EnvFilesInclude")]
+pub struct VarsFiles {
+    pub include: EnvFilesInclude,
+
+    /// Location of Devrcfile where files are defined
+    location: Location,
+
+    loading_config: LoadingConfig,
+
+    pub origin: Origin,
+}
+
+/// Variables loaded from data files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadedVariables {
+    pub variables: IndexMap<String, tera::Value>,
+
+    /// Values loaded from files marked as `secret`
+    pub secrets: IndexSet<String>,
+}
+
+impl From<EnvFilesInclude> for VarsFiles {
+    fn from(include: EnvFilesInclude) -> Self {
+        Self {
+            include,
+            ..Default::default()
+        }
+    }
+}
+
+impl VarsFiles {
+    pub fn setup_loading(&mut self, location: &Location, config: &LoadingConfig) {
+        self.location = location.clone();
+        self.loading_config = config.clone();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self.include, EnvFilesInclude::Empty)
+    }
+
+    pub fn load(&self) -> DevrcResult<LoadedVariables> {
+        let mut loaded = LoadedVariables::default();
+        load_include(
+            &self.include,
+            &self.location,
+            &self.loading_config,
+            &mut loaded,
+        )?;
+        Ok(loaded)
+    }
+}
+
+impl LoadedVariables {
+    fn extend(&mut self, variables: IndexMap<String, tera::Value>, secret: bool) {
+        if secret {
+            for value in variables.values() {
+                collect_strings(value, &mut self.secrets);
+            }
+        }
+        self.variables.extend(variables);
+    }
+}
+
+fn load_include(
+    include: &EnvFilesInclude,
+    location: &Location,
+    config: &LoadingConfig,
+    loaded: &mut LoadedVariables,
+) -> DevrcResult<()> {
+    let (name, content, ignore_errors, secret) = match include {
+        EnvFilesInclude::Empty => return Ok(()),
+        EnvFilesInclude::List(list) => {
+            for include in list {
+                load_include(include, location, config, loaded)?;
+            }
+            return Ok(());
+        }
+        EnvFilesInclude::Simple(path) => (
+            path.display().to_string(),
+            LocalFileImport::from(path).get_content(location.clone(), config.clone()),
+            false,
+            false,
+        ),
+        EnvFilesInclude::File(file) => (
+            file.file.display().to_string(),
+            file.get_content(location.clone(), config.clone()),
+            file.ignore_errors,
+            file.secret,
+        ),
+        EnvFilesInclude::Url(url) => (
+            url.url.clone(),
+            url.get_content(location.clone(), config.clone()),
+            url.ignore_errors,
+            url.secret,
+        ),
+    };
+
+    let variables = content.and_then(|content| parse_variables(&name, &content));
+
+    match variables {
+        Ok(variables) => loaded.extend(variables, secret),
+        Err(_) if ignore_errors => {}
+        Err(error) => return Err(error),
+    }
+    Ok(())
+}
+
+/// Parse JSON, TOML or YAML mapping, format is detected by file extension
+pub fn parse_variables(name: &str, content: &str) -> DevrcResult<IndexMap<String, tera::Value>> {
+    let format = ValueFormat::from_extension(name).unwrap_or(ValueFormat::Yaml);
+
+    match format.parse(name, content)? {
+        Some(tera::Value::Object(items)) => Ok(items.into_iter().collect()),
+        Some(tera::Value::Null) => Ok(IndexMap::new()),
+        _ => Err(DevrcError::VariableParseError {
+            name: name.to_string(),
+            reason: "variables file must contain a mapping".to_string(),
+        }),
+    }
+}
+
+/// Collect string values to mask them in output
+fn collect_strings(value: &tera::Value, result: &mut IndexSet<String>) {
+    match value {
+        tera::Value::String(value) => {
+            result.insert(value.clone());
+        }
+        tera::Value::Array(items) => {
+            for item in items {
+                collect_strings(item, result);
+            }
+        }
+        tera::Value::Object(items) => {
+            for item in items.values() {
+                collect_strings(item, result);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_variables() {
+        let variables =
+            parse_variables("envs/staging.yaml", "replicas: 2\nhost: staging\n").unwrap();
+        assert_eq!(variables.get("replicas"), Some(&tera::Value::from(2)));
+        assert_eq!(variables.get("host"), Some(&tera::Value::from("staging")));
+
+        let variables = parse_variables("vars.json", r#"{"ports": [80, 443]}"#).unwrap();
+        assert_eq!(
+            variables.get("ports"),
+            Some(&tera::Value::from(vec![80, 443]))
+        );
+
+        let variables =
+            parse_variables("https://example.com/vars.toml?ref=main", "name = \"app\"").unwrap();
+        assert_eq!(variables.get("name"), Some(&tera::Value::from("app")));
+
+        assert!(parse_variables("vars.yaml", "- item").is_err());
+    }
+
+    #[test]
+    fn test_load_vars_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("base.yaml"), "host: base\nport: 80\n").unwrap();
+        fs::write(dir.path().join("staging.json"), r#"{"host": "staging"}"#).unwrap();
+
+        let include: EnvFilesInclude = serde_yaml::from_str(&format!(
+            r#"
+- {0}/base.yaml
+- file: {0}/staging.json
+  secret: true
+- file: {0}/missing.yaml
+  ignore_errors: true
+"#,
+            dir.path().display()
+        ))
+        .unwrap();
+
+        let loaded = VarsFiles::from(include).load().unwrap();
+
+        assert_eq!(
+            loaded.variables.get("host"),
+            Some(&tera::Value::from("staging"))
+        );
+        assert_eq!(loaded.variables.get("port"), Some(&tera::Value::from(80)));
+        assert_eq!(loaded.secrets.iter().collect::<Vec<_>>(), vec!["staging"]);
+    }
+}
@@ -9,6 +9,9 @@ use crate::{
     plugin::Plugin,
 };
 
+/// Name of optional constructor of output capturing plugin
+const OUTPUT_PLUGIN_SYMBOL: &[u8] = b"_plugin_create_output";
+
 pub trait ExecutionPlugin: Plugin {
     fn execute(
         &self,
@@ -18,6 +21,19 @@ pub trait ExecutionPlugin: Plugin {
     ) -> DevrcPluginResult<i32>;
 }
 
+/// Capturing of standard output of executed code.
+///
+/// It's exported by library as separate optional constructor,
+/// so plugins built without it are still loaded.
+pub trait ExecutionOutputPlugin {
+    fn output(
+        &self,
+        execution_config: ExecutionConfig,
+        code: &str,
+        environment: &indexmap::IndexMap<String, String>,
+    ) -> DevrcPluginResult<String>;
+}
+
 /// Declare a plugin type and its constructor.
 ///
 /// # Notes
@@ -40,9 +56,32 @@ macro_rules! declare_execution_plugin {
     };
 }
 
+/// Declare a type which captures output of plugin and its constructor.
+///
+/// # Notes
+///
+/// Like `declare_execution_plugin` it generates `extern "C"` function,
+/// so only one type can be declared per library.
+#[macro_export]
+macro_rules! declare_execution_output_plugin {
+    ($plugin_type:ty, $constructor:path) => {
+        #[no_mangle]
+        pub extern "C" fn _plugin_create_output(
+        ) -> *mut dyn $crate::execution::ExecutionOutputPlugin {
+            // make sure the constructor is the correct type.
+            let constructor: fn() -> $plugin_type = $constructor;
+
+            let object = constructor();
+            let boxed: Box<dyn $crate::execution::ExecutionOutputPlugin> = Box::new(object);
+            Box::into_raw(boxed)
+        }
+    };
+}
+
 #[derive(Default)]
 pub struct ExecutionPluginManager {
     plugins: Vec<(String, Box<dyn ExecutionPlugin>)>,
+    output_plugins: Vec<(String, Box<dyn ExecutionOutputPlugin>)>,
     loaded_libraries: Vec<Library>,
     designer: Designer,
     logger: LogLevel,
@@ -58,6 +97,7 @@ impl ExecutionPluginManager {
     pub fn new() -> ExecutionPluginManager {
         ExecutionPluginManager {
             plugins: Vec::new(),
+            output_plugins: Vec::new(),
             loaded_libraries: Vec::new(),
             designer: Designer::default(),
             logger: LogLevel::default(),
@@ -78,6 +118,7 @@ impl ExecutionPluginManager {
         logger: LogLevel,
     ) -> DevrcPluginResult<()> {
         type PluginCreate = unsafe fn() -> *mut dyn ExecutionPlugin;
+        type OutputPluginCreate = unsafe fn() -> *mut dyn ExecutionOutputPlugin;
 
         let lib = Library::new(filename.as_ref())?;
 
@@ -93,6 +134,11 @@ impl ExecutionPluginManager {
 
         let mut plugin = Box::from_raw(boxed_raw);
 
+        if let Ok(constructor) = lib.get::<OutputPluginCreate>(OUTPUT_PLUGIN_SYMBOL) {
+            self.output_plugins
+                .push((name.to_string(), Box::from_raw(constructor())));
+        }
+
         logger.debug(
             &format!(
                 "\n==> Loading PLUGIN: `{}` as `{}` from `{:?}` ...",
@@ -124,6 +170,7 @@ impl ExecutionPluginManager {
             );
             plugin.on_plugin_unload();
         }
+        self.output_plugins.clear();
 
         for lib in self.loaded_libraries.drain(..) {
             drop(lib);
@@ -142,11 +189,25 @@ impl ExecutionPluginManager {
 
         Err(DevrcPluginError::NotFound(plugin_name.to_string()))
     }
+
+    /// Get output capturing part of plugin if library declares it
+    pub fn get_output_plugin(
+        &mut self,
+        plugin_name: &str,
+    ) -> Option<&Box<dyn ExecutionOutputPlugin>> {
+        self.output_plugins
+            .iter()
+            .find(|(name, _)| name == plugin_name)
+            .map(|(_, plugin)| plugin)
+    }
 }
 
 impl Drop for ExecutionPluginManager {
     fn drop(&mut self) {
-        if !self.plugins.is_empty() || !self.loaded_libraries.is_empty() {
+        if !self.plugins.is_empty()
+            || !self.output_plugins.is_empty()
+            || !self.loaded_libraries.is_empty()
+        {
             self.unload();
         }
     }
@@ -15,5 +15,5 @@ pub mod options;
 pub mod plugin;
 
 pub use errors::{DevrcPluginError, DevrcPluginResult};
-pub use execution::{ExecutionPlugin, ExecutionPluginManager};
+pub use execution::{ExecutionOutputPlugin, ExecutionPlugin, ExecutionPluginManager};
 pub use plugin::Plugin;